@@ -1,85 +1,454 @@
-use rusqlite::Connection;
-use structs::table_entree::TableEntree;
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{Connection, OptionalExtension};
+use structs::{
+    session::Session, table_entree::TableEntree, table_value::TableValue, topic_info::TopicInfo,
+    topic_tree::TopicSummary, write_record::WriteRecord,
+};
 
 pub mod structs;
 
+/// Bumped every time the layout of the tables changes. Stored in SQLite's `user_version` pragma.
+const SCHEMA_VERSION: u32 = 7;
+
+/// The topic catalog has a row for every topic of every network table connection (`source`, `''` for entries without one)
+const CREATE_TOPICS: &str = "CREATE TABLE topics (name TEXT NOT NULL, type TEXT, topic_id INTEGER, properties TEXT NOT NULL DEFAULT '{}', persistent INTEGER NOT NULL DEFAULT 0, retained INTEGER NOT NULL DEFAULT 0, cached INTEGER NOT NULL DEFAULT 1, announced INTEGER NOT NULL DEFAULT 0, first_seen INTEGER, last_seen INTEGER, source TEXT NOT NULL DEFAULT '', PRIMARY KEY (source, name))";
+
+/// The update rate of a topic is measured over this much of the end of a session (microseconds)
+const UPDATE_RATE_WINDOW: u64 = 10_000_000;
+
 #[derive(Debug)]
 pub struct SQLiteDatabase {
     connection: Connection,
-    last_update: u32,
-    min_time_between_cleans: u32,
+    /// Timestamp of the newest entry of the current session in microseconds, for every network table connection (`None` for
+    /// entries without a source). Every connection has its own robot clock, so their timestamps can not be compared
+    last_update: HashMap<Option<String>, u64>,
+    /// Microseconds
+    min_time_between_cleans: u64,
+    /// The session that new entries are added to
+    current_session: i64,
 }
 
 impl SQLiteDatabase {
-    pub fn new(file: &str, min_time_between_cleans: u32) -> Result<Self, rusqlite::Error> {
+    pub fn new(file: &str, min_time_between_cleans: u64) -> Result<Self, rusqlite::Error> {
         let connection = Connection::open(file)?;
-        let _ = connection.execute(
-            "CREATE TABLE IF NOT EXISTS data (topic TEXT, value TEXT, timestamp INTEGER)",
-            [],
-        );
+        Self::create_tables(&connection)?;
 
-        let self_inst = SQLiteDatabase {
+        let mut self_inst = SQLiteDatabase {
             connection,
-            last_update: 0,
-            min_time_between_cleans: min_time_between_cleans,
+            last_update: HashMap::new(),
+            min_time_between_cleans,
+            current_session: 0,
         };
 
-        let _ = self_inst.clear_database();
+        self_inst.start_session()?;
+        // nothing is connected yet, topics that were announced when the backend stopped are not anymore
+        self_inst.unannounce_topic(None, None)?;
 
         Ok(self_inst)
     }
 
+    ///
+    /// # Function
+    /// Creates the tables if they do not exist yet and upgrades databases made with an older layout (see `SCHEMA_VERSION`).
+    ///
+    /// # Schema
+    /// The `value` column has no declared type on purpose. This way SQLite stores numbers as numbers, text as text and
+    /// raw bytes as blobs (see `TableValue`) instead of converting everything to text.
+    ///
+    fn create_tables(connection: &Connection) -> Result<(), rusqlite::Error> {
+        let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 2 {
+            // Before version 2 the data was wiped on every start so there is nothing worth keeping
+            connection.execute("DROP TABLE IF EXISTS data", [])?;
+        }
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS sessions (id INTEGER PRIMARY KEY AUTOINCREMENT, start_time INTEGER, end_time INTEGER)",
+            [],
+        )?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS data (topic TEXT, type TEXT, value, timestamp INTEGER, session_id INTEGER REFERENCES sessions(id), decoded TEXT, wall_time INTEGER, source TEXT)",
+            [],
+        )?;
+        if version == 2 {
+            // Version 3 added the decoded fields of struct topics
+            connection.execute("ALTER TABLE data ADD COLUMN decoded TEXT", [])?;
+        }
+        if (2..4).contains(&version) {
+            // Version 4 added the wall clock time of every entry
+            connection.execute("ALTER TABLE data ADD COLUMN wall_time INTEGER", [])?;
+        }
+        if (2..5).contains(&version) {
+            // Version 5 added the network table connection that every entry came from
+            connection.execute("ALTER TABLE data ADD COLUMN source TEXT", [])?;
+        }
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS data_session_topic_timestamp ON data (session_id, topic, timestamp)",
+            [],
+        )?;
+        connection.execute(
+            "CREATE INDEX IF NOT EXISTS data_topic_wall_time ON data (topic, wall_time)",
+            [],
+        )?;
+        if version < 6 {
+            // Version 6 added the topic catalog and the audit log of writes. Older backends could already have made a catalog
+            // without a version, in one of several layouts. It only mirrors the announcements, so it is started over
+            connection.execute("DROP TABLE IF EXISTS topics", [])?;
+            connection.execute(CREATE_TOPICS, [])?;
+            connection.execute(
+                "CREATE TABLE IF NOT EXISTS writes (id INTEGER PRIMARY KEY AUTOINCREMENT, time INTEGER, topic TEXT, type TEXT, value, origin TEXT, client TEXT, error TEXT)",
+                [],
+            )?;
+        }
+        if version == 6 {
+            // Version 7 gave every network table connection its own rows in the catalog, instead of one row per topic name
+            connection.execute("ALTER TABLE topics RENAME TO topics_old", [])?;
+            connection.execute(CREATE_TOPICS, [])?;
+            connection.execute(
+                "INSERT INTO topics SELECT name, type, topic_id, properties, persistent, retained, cached, announced, first_seen, last_seen, COALESCE(source, '') FROM topics_old",
+                [],
+            )?;
+            connection.execute("DROP TABLE topics_old", [])?;
+        }
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Ends the current session and starts a new one. New entries are added to the new session.
+    /// If the current session has no entries (for example the backend just started and the network table connected right after) it is replaced instead of kept.
+    ///
+    /// # Returns
+    /// The id of the new session
+    ///
+    pub fn start_session(&mut self) -> Result<i64, rusqlite::Error> {
+        self.connection.execute(
+            "DELETE FROM sessions WHERE id = ? AND NOT EXISTS (SELECT 1 FROM data WHERE session_id = ?)",
+            [self.current_session, self.current_session],
+        )?;
+        self.end_session()?;
+
+        self.connection.execute(
+            "INSERT INTO sessions (start_time, end_time) VALUES (?, NULL)",
+            [now_micros()],
+        )?;
+
+        self.current_session = self.connection.last_insert_rowid();
+        self.last_update.clear();
+
+        Ok(self.current_session)
+    }
+
+    ///
+    /// # Function
+    /// Marks the current session as ended. Entries that are added afterwards still go into it until `start_session` is called.
+    ///
+    pub fn end_session(&self) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "UPDATE sessions SET end_time = ? WHERE id = ? AND end_time IS NULL",
+            rusqlite::params![now_micros(), self.current_session],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn current_session(&self) -> i64 {
+        self.current_session
+    }
+
+    ///
+    /// # Function
+    /// Gets all the recording sessions, newest first.
+    ///
+    pub fn get_sessions(&self) -> Result<Vec<Session>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT id, start_time, end_time, (SELECT COUNT(*) FROM data WHERE session_id = sessions.id) FROM sessions ORDER BY id DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(Session {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                entries: row.get(3)?,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<Session>, rusqlite::Error>>()
+    }
+
+    #[cfg(test)]
     pub fn get_values(
         &self,
         topic: &str,
-        min_time_since_last_update: u32,
+        min_time_since_last_update: u64,
+        max_count: u32,
+    ) -> Result<Vec<TableEntree>, rusqlite::Error> {
+        self.get_session_values(
+            self.current_session,
+            topic,
+            None,
+            min_time_since_last_update,
+            max_count,
+        )
+    }
+
+    ///
+    /// # Function
+    /// Same as `get_values` but for any session. Timestamps from different sessions can not be compared (the robot clock resets),
+    /// so `min_time_since_last_update` is relative to the newest entry of that session.
+    /// `source` gets the entries of one network table connection. The connections have their own clocks, so `None` gets the
+    /// entries of the connection that updated the topic last (see `read_source`).
+    ///
+    pub fn get_session_values(
+        &self,
+        session: i64,
+        topic: &str,
+        source: Option<&str>,
+        min_time_since_last_update: u64,
         max_count: u32,
     ) -> Result<Vec<TableEntree>, rusqlite::Error> {
+        let Some(source) = self.read_source(session, topic, source)? else {
+            return Ok(Vec::new());
+        };
+        let last_update = self.session_last_update(session, source.as_deref())?;
         let mut stmt = self.connection.prepare(
-            "SELECT value, timestamp FROM data WHERE topic = ? AND timestamp >= ? ORDER BY timestamp DESC LIMIT ?"
+            "SELECT type, value, timestamp, decoded, wall_time, source FROM data WHERE session_id = ?1 AND topic = ?2 AND source IS ?3 AND timestamp >= ?4 ORDER BY timestamp DESC LIMIT ?5"
         )?;
 
         let rows = stmt.query_map(
-            [
+            rusqlite::params![
+                session,
                 topic,
-                &(self.last_update as i32 - min_time_since_last_update as i32).to_string(),
-                &max_count.to_string(),
+                source,
+                last_update.saturating_sub(min_time_since_last_update),
+                max_count,
             ],
-            |row| {
-                Ok(TableEntree {
-                    topic: topic.to_string(),
-                    value: row.get(0)?,
-                    timestamp: row.get(1)?,
-                })
-            },
+            |row| entree_from_row(topic, row),
         )?;
 
-        Ok(rows.collect::<Result<Vec<TableEntree>, rusqlite::Error>>()?)
+        rows.collect::<Result<Vec<TableEntree>, rusqlite::Error>>()
+    }
+
+    ///
+    /// # Function
+    /// Gets the newest entries of a topic by their wall clock time, from every session. Because wall clock times keep going
+    /// up across robot reboots, the sessions line up. Entries without a wall clock time (recorded before it was added) are left out.
+    ///
+    /// # Parameters
+    /// - `topic`: The topic to get the entries of
+    /// - `source`: Only get the entries of this network table connection. `None` gets the entries of every connection
+    /// - `since`: Only get entries with a wall clock time (UNIX microseconds) of at least this
+    /// - `max_count`: The maximum amount of entries, the newest ones are returned
+    ///
+    pub fn get_wall_time_values(
+        &self,
+        topic: &str,
+        source: Option<&str>,
+        since: u64,
+        max_count: u32,
+    ) -> Result<Vec<TableEntree>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT type, value, timestamp, decoded, wall_time, source FROM data WHERE topic = ?1 AND (?2 IS NULL OR source = ?2) AND wall_time >= ?3 ORDER BY wall_time DESC LIMIT ?4"
+        )?;
+
+        let rows = stmt.query_map(rusqlite::params![topic, source, since, max_count], |row| {
+            entree_from_row(topic, row)
+        })?;
+
+        rows.collect::<Result<Vec<TableEntree>, rusqlite::Error>>()
+    }
+
+    ///
+    /// # Function
+    /// Gets the value a topic had at a time of a session: the entry at that time, or the newest one before it.
+    ///
+    /// # Parameters
+    /// - `session`: The session to look in
+    /// - `topic`: The topic to get the entry of
+    /// - `source`: The network table connection whose clock `timestamp` is in. `None` uses the connection that updated the topic last
+    /// - `timestamp`: The time (microseconds, robot time)
+    ///
+    /// # Returns
+    /// `None` if the topic has no entry at or before that time
+    ///
+    pub fn get_value_at(
+        &self,
+        session: i64,
+        topic: &str,
+        source: Option<&str>,
+        timestamp: u64,
+    ) -> Result<Option<TableEntree>, rusqlite::Error> {
+        let Some(source) = self.read_source(session, topic, source)? else {
+            return Ok(None);
+        };
+        self.connection
+            .query_row(
+                "SELECT type, value, timestamp, decoded, wall_time, source FROM data WHERE session_id = ?1 AND topic = ?2 AND source IS ?3 AND timestamp <= ?4 ORDER BY timestamp DESC LIMIT 1",
+                rusqlite::params![session, topic, source, timestamp],
+                |row| entree_from_row(topic, row),
+            )
+            .optional()
+    }
+
+    ///
+    /// # Function
+    /// Finds the network table connection whose entries are read for a topic. Every connection has its own clock, so the
+    /// entries of different connections can not be ordered by their timestamps. Without a `source` the connection that
+    /// updated the topic last (the newest row) is used.
+    ///
+    /// # Returns
+    /// The source (`Some(None)` for entries without one), or `None` if the topic has no entries in the session
+    ///
+    fn read_source(
+        &self,
+        session: i64,
+        topic: &str,
+        source: Option<&str>,
+    ) -> Result<Option<Option<String>>, rusqlite::Error> {
+        if let Some(source) = source {
+            return Ok(Some(Some(source.to_string())));
+        }
+
+        self.connection
+            .query_row(
+                "SELECT source FROM data WHERE session_id = ? AND topic = ? ORDER BY rowid DESC LIMIT 1",
+                rusqlite::params![session, topic],
+                |row| row.get(0),
+            )
+            .optional()
     }
 
+    /// The timestamp of the newest entry of a network table connection in a session
+    fn session_last_update(
+        &self,
+        session: i64,
+        source: Option<&str>,
+    ) -> Result<u64, rusqlite::Error> {
+        if session == self.current_session {
+            return Ok(self
+                .last_update
+                .get(&source.map(str::to_string))
+                .copied()
+                .unwrap_or(0));
+        }
+
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT MAX(timestamp) FROM data WHERE session_id = ? AND source IS ?",
+                rusqlite::params![session, source],
+                |row| row.get::<_, Option<u64>>(0),
+            )
+            .optional()?
+            .flatten()
+            .unwrap_or(0))
+    }
+
+    #[cfg(test)]
     pub fn get_values_no_time(
         &self,
         topic: &str,
         max_count: u32,
     ) -> Result<Vec<TableEntree>, rusqlite::Error> {
-        self.get_values(topic, self.last_update, max_count)
+        self.get_values(topic, u64::MAX, max_count)
     }
 
     pub fn get_value(&self, topic: &str) -> Result<TableEntree, rusqlite::Error> {
+        self.get_session_value(self.current_session, topic, None)
+    }
+
+    pub fn get_session_value(
+        &self,
+        session: i64,
+        topic: &str,
+        source: Option<&str>,
+    ) -> Result<TableEntree, rusqlite::Error> {
         Ok(self
-            .get_values(topic, self.last_update, 1)?
-            .get(0)
+            .get_session_values(session, topic, source, u64::MAX, 1)?
+            .first()
             .unwrap_or(&TableEntree::get_error())
             .clone())
     }
 
+    #[cfg(test)]
     pub fn add_value(&mut self, data: TableEntree) -> Result<(), rusqlite::Error> {
         self.connection.execute(
-            "INSERT INTO data (topic, value, timestamp) VALUES (?, ?, ?)",
-            [data.topic, data.value, data.timestamp.clone().to_string()],
+            "INSERT INTO data (topic, type, value, timestamp, session_id, decoded, wall_time, source) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                data.topic,
+                data.value_type,
+                data.value,
+                data.timestamp,
+                self.current_session,
+                data.decoded.as_ref().map(|decoded| decoded.to_string()),
+                data.wall_time,
+                data.source
+            ],
         )?;
 
-        self.last_update = data.timestamp.clone();
+        self.last_update.insert(data.source, data.timestamp);
+
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Adds many entries at once inside a single transaction. This is a lot faster than calling `add_value` for every entry
+    /// because SQLite only has to write to the disk once. Either all the entries are added or none of them are.
+    /// The topics of the entries are added to the topic catalog (if they are not in it yet) and their `last_seen` is updated.
+    ///
+    /// # Parameters
+    /// - `data`: The entries that will be added to the current session, in the order they were received
+    ///
+    pub fn add_values(&mut self, data: &[TableEntree]) -> Result<(), rusqlite::Error> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut stmt = transaction.prepare_cached(
+                "INSERT INTO data (topic, type, value, timestamp, session_id, decoded, wall_time, source) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+
+            for entree in data {
+                stmt.execute(rusqlite::params![
+                    entree.topic,
+                    entree.value_type,
+                    entree.value,
+                    entree.timestamp,
+                    self.current_session,
+                    entree.decoded.as_ref().map(|decoded| decoded.to_string()),
+                    entree.wall_time,
+                    entree.source
+                ])?;
+            }
+
+            let mut stmt = transaction.prepare_cached(
+                "INSERT INTO topics (name, type, first_seen, last_seen, source) VALUES (?1, ?2, ?3, ?3, COALESCE(?4, '')) ON CONFLICT(source, name) DO UPDATE SET last_seen = excluded.last_seen",
+            )?;
+            let now = now_micros();
+            let mut seen = std::collections::HashSet::new();
+            for entree in data {
+                if seen.insert((entree.topic.as_str(), entree.source.as_deref())) {
+                    stmt.execute(rusqlite::params![
+                        entree.topic,
+                        entree.value_type,
+                        now,
+                        entree.source
+                    ])?;
+                }
+            }
+        }
+        transaction.commit()?;
+
+        for entree in data {
+            self.last_update
+                .insert(entree.source.clone(), entree.timestamp);
+        }
 
         Ok(())
     }
@@ -91,46 +460,311 @@ impl SQLiteDatabase {
         Ok(())
     }*/
 
+    ///
+    /// # Function
+    /// Adds a topic that the server announced to the topic catalog, or updates it if it is already in there.
+    /// `first_seen` is kept from the first time the topic was seen. Every network table connection has its own rows, so two
+    /// connections with the same topic are two entries of the catalog.
+    ///
+    pub fn announce_topic(&self, topic: &TopicInfo) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "INSERT INTO topics (name, type, topic_id, properties, persistent, retained, cached, announced, first_seen, last_seen, source) VALUES (?, ?, ?, ?, ?, ?, ?, 1, ?, ?, COALESCE(?, ''))
+            ON CONFLICT(source, name) DO UPDATE SET type = excluded.type, topic_id = excluded.topic_id, properties = excluded.properties, persistent = excluded.persistent,
+            retained = excluded.retained, cached = excluded.cached, announced = 1, last_seen = excluded.last_seen",
+            rusqlite::params![
+                topic.name,
+                topic.value_type,
+                topic.id,
+                topic.properties.to_string(),
+                topic.persistent,
+                topic.retained,
+                topic.cached,
+                topic.first_seen,
+                topic.last_seen,
+                topic.source
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Marks a topic of the catalog as not announced anymore. The topic stays in the catalog.
+    ///
+    /// # Parameters
+    /// - `name`: The topic, or `None` for every topic (used when the connection is lost)
+    /// - `source`: Only change the topics of this network table connection, `None` changes the topics of every connection
+    ///
+    pub fn unannounce_topic(
+        &self,
+        name: Option<&str>,
+        source: Option<&str>,
+    ) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "UPDATE topics SET announced = 0, topic_id = NULL, last_seen = ?1 WHERE announced = 1 AND (?2 IS NULL OR name = ?2) AND (?3 IS NULL OR source = ?3)",
+            rusqlite::params![now_micros(), name, source],
+        )?;
+
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Gets the topic catalog, sorted by name (and by source for a topic that more than one network table connection has).
+    ///
+    /// # Parameters
+    /// - `prefix`: Only get the topics that start with this (`""` for all of them)
+    ///
+    pub fn get_topics(&self, prefix: &str) -> Result<Vec<TopicInfo>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT name, type, topic_id, properties, persistent, retained, cached, announced, first_seen, last_seen, COALESCE(counts.rows, 0), NULLIF(topics.source, '')
+            FROM topics LEFT JOIN (SELECT topic, COALESCE(source, '') AS source, COUNT(*) AS rows FROM data GROUP BY topic, COALESCE(source, '')) counts
+            ON counts.topic = topics.name AND counts.source = topics.source
+            WHERE substr(name, 1, length(?1)) = ?1 ORDER BY name, topics.source",
+        )?;
+
+        let rows = stmt.query_map([prefix], |row| {
+            let properties: String = row.get(3)?;
+
+            Ok(TopicInfo {
+                name: row.get(0)?,
+                value_type: row.get(1)?,
+                id: row.get(2)?,
+                properties: serde_json::from_str(&properties).unwrap_or_default(),
+                persistent: row.get(4)?,
+                retained: row.get(5)?,
+                cached: row.get(6)?,
+                announced: row.get(7)?,
+                first_seen: row.get(8)?,
+                last_seen: row.get(9)?,
+                rows: row.get(10)?,
+                source: row.get(11)?,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<TopicInfo>, rusqlite::Error>>()
+    }
+
+    ///
+    /// # Function
+    /// Gets the newest entry and the update rate of every topic of a session. The update rate is measured over the last
+    /// `UPDATE_RATE_WINDOW` of the session (on the clock of the network table connection of the entry), so a topic that
+    /// stopped updating has a rate of `0.0`.
+    ///
+    /// # Parameters
+    /// - `session`: The session to get the topics of
+    /// - `prefix`: Only get the topics that start with this (`""` for all of them). A leading `/` is ignored on both sides
+    /// - `source`: Only get the topics of this network table connection. With `None` a topic that comes from more than one
+    ///   connection is one topic, its newest entry and update rate are from whichever connection updated it last
+    ///
+    pub fn get_topic_summaries(
+        &self,
+        session: i64,
+        prefix: &str,
+        source: Option<&str>,
+    ) -> Result<Vec<TopicSummary>, rusqlite::Error> {
+        // the newest entry is the newest row, because the timestamps of the connections can not be compared.
+        // SQLite takes the other columns from the row with the MAX(rowid)
+        let mut stmt = self.connection.prepare(
+            "WITH clocks AS (SELECT source, MAX(timestamp) AS last_update FROM data WHERE session_id = ?1 GROUP BY source)
+            SELECT latest.topic, latest.type, latest.value, latest.timestamp, COALESCE(recent.updates, 0), recent.first, recent.last, latest.decoded, latest.wall_time, latest.source
+            FROM (SELECT topic, type, value, timestamp, decoded, wall_time, source, MAX(rowid) FROM data WHERE session_id = ?1 AND (?4 IS NULL OR source = ?4) GROUP BY topic) latest
+            LEFT JOIN (SELECT topic, data.source, COUNT(*) AS updates, MIN(timestamp) AS first, MAX(timestamp) AS last FROM data JOIN clocks ON clocks.source IS data.source
+                WHERE session_id = ?1 AND timestamp >= clocks.last_update - ?3 GROUP BY topic, data.source) recent
+            ON recent.topic = latest.topic AND recent.source IS latest.source
+            WHERE substr(ltrim(latest.topic, '/'), 1, length(?2)) = ?2 ORDER BY latest.topic",
+        )?;
+
+        let rows = stmt.query_map(
+            rusqlite::params![
+                session,
+                prefix.trim_start_matches('/'),
+                UPDATE_RATE_WINDOW,
+                source
+            ],
+            |row| {
+                let topic: String = row.get(0)?;
+                let value_type: String = row.get(1)?;
+                // one row that can not be read should not hide every other topic
+                let value = match TableValue::from_sql(&value_type, row.get(2)?) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        println!("Skipping the newest value of {}: {}", topic, err);
+                        return Ok(None);
+                    }
+                };
+                let updates: u64 = row.get(4)?;
+                let first: Option<u64> = row.get(5)?;
+                let last: Option<u64> = row.get(6)?;
+                let update_rate = match (first, last) {
+                    (Some(first), Some(last)) if updates > 1 && last > first => {
+                        (updates - 1) as f64 / ((last - first) as f64 / 1_000_000.0)
+                    }
+                    _ => 0.0,
+                };
+
+                let mut latest = TableEntree::with_type(topic, value_type, value, row.get(3)?);
+                latest.decoded = decoded_from_sql(row.get(7)?);
+                latest.wall_time = row.get(8)?;
+                latest.source = row.get(9)?;
+
+                Ok(Some(TopicSummary {
+                    latest,
+                    update_rate,
+                }))
+            },
+        )?;
+
+        rows.filter_map(Result::transpose)
+            .collect::<Result<Vec<TopicSummary>, rusqlite::Error>>()
+    }
+
+    ///
+    /// # Function
+    /// Adds an entry to the audit log of writes to the network table. The audit log is never cleaned or cleared.
+    ///
+    pub fn add_write(&self, record: &WriteRecord) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "INSERT INTO writes (time, topic, type, value, origin, client, error) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                record.time,
+                record.topic,
+                record.value_type,
+                record.value,
+                record.origin,
+                record.client,
+                record.error
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Gets the newest entries of the audit log of writes to the network table, newest first.
+    ///
+    pub fn get_writes(&self, max_count: u32) -> Result<Vec<WriteRecord>, rusqlite::Error> {
+        let mut stmt = self.connection.prepare(
+            "SELECT time, topic, type, value, origin, client, error FROM writes ORDER BY id DESC LIMIT ?",
+        )?;
+
+        let rows = stmt.query_map([max_count], |row| {
+            let value_type: String = row.get(2)?;
+            let value = TableValue::from_sql(&value_type, row.get(3)?).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    3,
+                    rusqlite::types::Type::Null,
+                    Box::new(err),
+                )
+            })?;
+
+            Ok(WriteRecord {
+                time: row.get(0)?,
+                topic: row.get(1)?,
+                value_type,
+                value,
+                origin: row.get(4)?,
+                client: row.get(5)?,
+                error: row.get(6)?,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<WriteRecord>, rusqlite::Error>>()
+    }
+
     pub fn clean_database(&self) -> Result<(), rusqlite::Error> {
         self.clean_database_time(self.min_time_between_cleans)
     }
 
+    #[cfg(test)]
     pub fn length(&self) -> Result<u32, rusqlite::Error> {
         let mut binding = self.connection.prepare("SELECT COUNT(*) FROM data")?;
         let mut stmt = binding.query([])?;
-        Ok(stmt.next()?.unwrap().get(0)?)
+        stmt.next()?.unwrap().get(0)
     }
 
+    #[cfg(test)]
     pub fn topic_length(&self, topic: &str) -> Result<u32, rusqlite::Error> {
         let mut binding = self
             .connection
             .prepare("SELECT COUNT(*) FROM data WHERE topic = ?")?;
         let mut stmt = binding.query([topic])?;
-        Ok(stmt.next()?.unwrap().get(0)?)
+        stmt.next()?.unwrap().get(0)
     }
 
+    ///
+    /// # Function
+    /// Removes all the entries, all the sessions other than the current one and the topics that are not announced right now.
+    ///
     pub fn clear_database(&self) -> Result<(), rusqlite::Error> {
         self.connection.execute("DELETE FROM data", [])?;
+        self.connection
+            .execute("DELETE FROM topics WHERE announced = 0", [])?;
+        self.connection
+            .execute("DELETE FROM sessions WHERE id != ?", [self.current_session])?;
         Ok(())
     }
 
+    ///
+    /// # Function
+    /// Removes the entries of the current session that are older than `last_update - min_time_since_last_update`, on the clock of
+    /// every network table connection. Older sessions are not touched because their timestamps come from a different robot clock.
+    ///
     pub fn clean_database_time(
         &self,
-        min_time_since_last_update: u32,
+        min_time_since_last_update: u64,
     ) -> Result<(), rusqlite::Error> {
-        if self.last_update < min_time_since_last_update {
-            return Ok(());
-        }
+        for (source, last_update) in &self.last_update {
+            if *last_update < min_time_since_last_update {
+                continue;
+            }
 
-        self.connection.execute(
-            "DELETE FROM data WHERE timestamp <= ?",
-            [(self.last_update - min_time_since_last_update).to_string()],
-        )?;
+            self.connection.execute(
+                "DELETE FROM data WHERE session_id = ? AND source IS ? AND timestamp <= ?",
+                rusqlite::params![
+                    self.current_session,
+                    source,
+                    last_update - min_time_since_last_update
+                ],
+            )?;
+        }
 
         Ok(())
     }
 }
 
+///
+/// # Function
+/// Gets the wall clock time in UNIX microseconds. Used for the session start and end times.
+///
+pub fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// Reads an entry from a row of `type, value, timestamp, decoded, wall_time, source`
+fn entree_from_row(topic: &str, row: &rusqlite::Row) -> Result<TableEntree, rusqlite::Error> {
+    let value_type: String = row.get(0)?;
+    let value = TableValue::from_sql(&value_type, row.get(1)?).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Null, Box::new(err))
+    })?;
+
+    let mut entree = TableEntree::with_type(topic.to_string(), value_type, value, row.get(2)?);
+    entree.decoded = decoded_from_sql(row.get(3)?);
+    entree.wall_time = row.get(4)?;
+    entree.source = row.get(5)?;
+    Ok(entree)
+}
+
+/// Reads the `decoded` column, which is JSON text or `NULL`
+fn decoded_from_sql(decoded: Option<String>) -> Option<serde_json::Value> {
+    decoded.and_then(|decoded| serde_json::from_str(&decoded).ok())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -140,7 +774,7 @@ mod test {
 
         use super::*;
 
-        pub fn get_database(min_time_between_cleans: u32) -> SQLiteDatabase {
+        pub fn get_database(min_time_between_cleans: u64) -> SQLiteDatabase {
             if !std::path::Path::new("test.db").exists() {
                 let _ = File::create("test.db"); // create an empty file
             }
@@ -152,13 +786,13 @@ mod test {
 
         pub fn put_data_in_database(
             mut database: SQLiteDatabase,
-            data_amt: u32,
-            time_step: u32,
+            data_amt: u64,
+            time_step: u64,
         ) -> SQLiteDatabase {
             for i in 0..data_amt {
                 let _ = database.add_value(TableEntree::new(
                     "test".to_string(),
-                    "test".to_string(),
+                    TableValue::String("test".to_string()),
                     i * time_step,
                 ));
             }
@@ -172,7 +806,11 @@ mod test {
     fn test_clean_and_new() {
         let mut database = utils::get_database(2);
         database
-            .add_value(TableEntree::new("test".to_string(), "test".to_string(), 1))
+            .add_value(TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                1,
+            ))
             .unwrap();
         drop(database);
 
@@ -186,7 +824,11 @@ mod test {
     fn test_topic_length() {
         let mut database = utils::put_data_in_database(utils::get_database(2), 5, 1);
         database
-            .add_value(TableEntree::new("test1".to_string(), "test".to_string(), 1))
+            .add_value(TableEntree::new(
+                "test1".to_string(),
+                TableValue::String("test".to_string()),
+                1,
+            ))
             .unwrap();
         assert_eq!(database.topic_length("test").unwrap(), 5);
     }
@@ -203,7 +845,11 @@ mod test {
     fn test_add_value() {
         let mut database = utils::get_database(2);
         database
-            .add_value(TableEntree::new("test".to_string(), "test".to_string(), 1))
+            .add_value(TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                1,
+            ))
             .unwrap();
 
         assert!(database.get_value("test").is_ok());
@@ -233,4 +879,484 @@ mod test {
 
         assert_eq!(database.length().unwrap(), 2);
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_typed_values() {
+        let mut database = utils::get_database(2);
+        let values = [
+            TableValue::Double(1.5),
+            TableValue::Int(-7),
+            TableValue::Boolean(true),
+            TableValue::DoubleArray(vec![0.5, 2.0]),
+            TableValue::StringArray(vec!["a".to_string(), "b".to_string()]),
+            TableValue::Raw(vec![0, 1, 255]),
+        ];
+
+        for (i, value) in values.iter().enumerate() {
+            database
+                .add_value(TableEntree::new(format!("typed{}", i), value.clone(), 1))
+                .unwrap();
+        }
+
+        for (i, value) in values.iter().enumerate() {
+            let entree = database.get_value(&format!("typed{}", i)).unwrap();
+            assert_eq!(&entree.value, value);
+            assert_eq!(entree.value_type, value.type_name());
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_numeric_query() {
+        let mut database = utils::get_database(2);
+        for i in 0..4 {
+            database
+                .add_value(TableEntree::new(
+                    "numeric".to_string(),
+                    TableValue::Double(i as f64),
+                    i,
+                ))
+                .unwrap();
+        }
+
+        let average: f64 = database
+            .connection
+            .query_row(
+                "SELECT AVG(value) FROM data WHERE topic = 'numeric' AND value > 0.5",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(average, 2.0);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_restart_keeps_sessions() {
+        let database = utils::put_data_in_database(utils::get_database(2), 5, 1);
+        let old_session = database.current_session();
+        drop(database);
+
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        assert_ne!(database.current_session(), old_session);
+        assert_eq!(database.length().unwrap(), 5);
+        assert_eq!(
+            database.get_value("test").unwrap(),
+            TableEntree::get_error()
+        );
+        assert_eq!(
+            database
+                .get_session_values(old_session, "test", None, 2, 5)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_upgrade_unversioned_catalog() {
+        let _ = std::fs::remove_file("test.db");
+        {
+            // a version 4 database with the catalog that was made before it had a version (no source column)
+            let connection = Connection::open("test.db").unwrap();
+            connection
+                .execute_batch(
+                    "CREATE TABLE sessions (id INTEGER PRIMARY KEY AUTOINCREMENT, start_time INTEGER, end_time INTEGER);
+                    CREATE TABLE data (topic TEXT, type TEXT, value, timestamp INTEGER, session_id INTEGER REFERENCES sessions(id), decoded TEXT, wall_time INTEGER);
+                    CREATE TABLE topics (name TEXT PRIMARY KEY, type TEXT, topic_id INTEGER, properties TEXT NOT NULL DEFAULT '{}', persistent INTEGER NOT NULL DEFAULT 0, retained INTEGER NOT NULL DEFAULT 0, cached INTEGER NOT NULL DEFAULT 1, announced INTEGER NOT NULL DEFAULT 0, first_seen INTEGER, last_seen INTEGER);
+                    INSERT INTO sessions (start_time, end_time) VALUES (1, 2);
+                    INSERT INTO data VALUES ('test', 'double', 1.0, 1, 1, NULL, NULL);
+                    INSERT INTO topics (name, type) VALUES ('test', 'double');
+                    PRAGMA user_version = 4;",
+                )
+                .unwrap();
+        }
+
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        let version: u32 = database
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        assert_eq!(database.length().unwrap(), 1);
+
+        let mut topic = TopicInfo::from_nt3("test".to_string(), "double".to_string(), 1, false);
+        topic.source = Some("robot".to_string());
+        database.announce_topic(&topic).unwrap();
+        let topics = database.get_topics("").unwrap();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].source, Some("robot".to_string()));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_upgrade_catalog_per_source() {
+        let _ = std::fs::remove_file("test.db");
+        {
+            // a version 6 database, the catalog had one row per topic name
+            let connection = Connection::open("test.db").unwrap();
+            connection
+                .execute_batch(
+                    "CREATE TABLE topics (name TEXT PRIMARY KEY, type TEXT, topic_id INTEGER, properties TEXT NOT NULL DEFAULT '{}', persistent INTEGER NOT NULL DEFAULT 0, retained INTEGER NOT NULL DEFAULT 0, cached INTEGER NOT NULL DEFAULT 1, announced INTEGER NOT NULL DEFAULT 0, first_seen INTEGER, last_seen INTEGER, source TEXT);
+                    INSERT INTO topics (name, type, first_seen, last_seen, source) VALUES ('/Drive/Speed', 'double', 1, 2, 'robot'), ('/Old', 'int', 1, 2, NULL);
+                    PRAGMA user_version = 6;",
+                )
+                .unwrap();
+        }
+
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        let mut topic =
+            TopicInfo::from_nt3("/Drive/Speed".to_string(), "double".to_string(), 1, false);
+        topic.source = Some("sim".to_string());
+        database.announce_topic(&topic).unwrap();
+
+        let topics: Vec<(String, Option<String>, u64)> = database
+            .get_topics("")
+            .unwrap()
+            .into_iter()
+            .map(|topic| (topic.name, topic.source, topic.first_seen))
+            .collect();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(
+            topics[0],
+            ("/Drive/Speed".to_string(), Some("robot".to_string()), 1)
+        );
+        assert_eq!(topics[1].1, Some("sim".to_string()));
+        assert_eq!(topics[2], ("/Old".to_string(), None, 1));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_start_session_replaces_empty_session() {
+        let mut database = utils::get_database(2);
+        database.start_session().unwrap();
+        database.start_session().unwrap();
+
+        let sessions = database.get_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, database.current_session());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_add_values() {
+        let mut database = utils::get_database(2);
+        let entries: Vec<TableEntree> = (0..10)
+            .map(|i| TableEntree::new("batch".to_string(), TableValue::Int(i), i as u64))
+            .collect();
+
+        database.add_values(&entries).unwrap();
+
+        assert_eq!(database.topic_length("batch").unwrap(), 10);
+        assert_eq!(database.get_value("batch").unwrap(), entries[9]);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_topic_catalog() {
+        let mut database = utils::get_database(2);
+        database.unannounce_topic(None, None).unwrap();
+        database.clear_database().unwrap();
+
+        let mut announced = TopicInfo {
+            name: "/Catalog/kP".to_string(),
+            value_type: "double".to_string(),
+            id: Some(3),
+            properties: serde_json::json!({"persistent": true}),
+            persistent: true,
+            retained: false,
+            cached: true,
+            announced: true,
+            first_seen: 10,
+            last_seen: 10,
+            rows: 0,
+            source: None,
+        };
+        database.announce_topic(&announced).unwrap();
+        database
+            .add_values(&[
+                TableEntree::new("/Catalog/kP".to_string(), TableValue::Double(1.0), 0),
+                TableEntree::new("/Catalog/kP".to_string(), TableValue::Double(2.0), 1),
+                TableEntree::new("/Catalog/Other".to_string(), TableValue::Int(1), 1),
+                TableEntree::new("/Elsewhere".to_string(), TableValue::Int(1), 1),
+            ])
+            .unwrap();
+
+        let topics = database.get_topics("/Catalog/").unwrap();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].name, "/Catalog/Other");
+        assert!(!topics[0].announced);
+        assert_eq!(topics[0].value_type, "int");
+        assert_eq!(topics[1].rows, 2);
+        assert_eq!(topics[1].first_seen, 10);
+        assert!(topics[1].last_seen > 10);
+        assert!(topics[1].persistent);
+
+        database
+            .unannounce_topic(Some("/Catalog/kP"), None)
+            .unwrap();
+        let topic = &database.get_topics("/Catalog/kP").unwrap()[0];
+        assert!(!topic.announced);
+        assert_eq!(topic.id, None);
+
+        announced.id = Some(4);
+        database.announce_topic(&announced).unwrap();
+        let topic = &database.get_topics("/Catalog/kP").unwrap()[0];
+        assert!(topic.announced);
+        assert_eq!(topic.id, Some(4));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_topic_summaries() {
+        let mut database = utils::get_database(2);
+        database.clear_database().unwrap();
+
+        // 11 updates in 0.2 seconds
+        let mut entries: Vec<TableEntree> = (0..=10)
+            .map(|i| {
+                TableEntree::new(
+                    "/Drive/Speed".to_string(),
+                    TableValue::Double(i as f64),
+                    20_000_000 + i * 20_000,
+                )
+            })
+            .collect();
+        entries.push(TableEntree::new(
+            "/Drive/Old".to_string(),
+            TableValue::Int(1),
+            1_000_000,
+        ));
+        entries.push(TableEntree::new(
+            "/Arm/Angle".to_string(),
+            TableValue::Double(0.0),
+            20_000_000,
+        ));
+        // a row that does not fit its type is left out instead of failing the whole list
+        entries.push(TableEntree::with_type(
+            "/Drive/Broken".to_string(),
+            "boolean".to_string(),
+            TableValue::Double(0.5),
+            20_000_000,
+        ));
+        database.add_values(&entries).unwrap();
+
+        let summaries = database
+            .get_topic_summaries(database.current_session(), "/Drive", None)
+            .unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].latest.topic, "/Drive/Old");
+        assert_eq!(summaries[0].update_rate, 0.0);
+        assert_eq!(summaries[1].latest.value, TableValue::Double(10.0));
+        assert!((summaries[1].update_rate - 50.0).abs() < 0.001);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_decoded_structs() {
+        let mut database = utils::get_database(2);
+        let mut entree = TableEntree::with_type(
+            "/Drive/Pose".to_string(),
+            "struct:Rotation2d".to_string(),
+            TableValue::Raw(0.5f64.to_le_bytes().to_vec()),
+            1,
+        );
+        entree.decoded = Some(serde_json::json!({"value": 0.5}));
+        database
+            .add_values(&[
+                entree.clone(),
+                TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(1.0), 1),
+            ])
+            .unwrap();
+
+        assert_eq!(database.get_value("/Drive/Pose").unwrap(), entree);
+        assert_eq!(database.get_value("/Drive/Speed").unwrap().decoded, None);
+        let summaries = database
+            .get_topic_summaries(database.current_session(), "/Drive/Pose", None)
+            .unwrap();
+        assert_eq!(summaries[0].latest, entree);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_raw_values() {
+        let mut database = utils::get_database(2);
+        let bytes = vec![0x00, 0xff, 0xc3, 0x28, 0x10];
+        database
+            .add_values(&[
+                TableEntree::with_type(
+                    "/photonvision/rawBytes".to_string(),
+                    "raw".to_string(),
+                    TableValue::Raw(bytes.clone()),
+                    10,
+                ),
+                TableEntree::with_type(
+                    "/photonvision/rawBytes".to_string(),
+                    "raw".to_string(),
+                    TableValue::Raw(vec![1]),
+                    20,
+                ),
+            ])
+            .unwrap();
+
+        let storage: String = database
+            .connection
+            .query_row(
+                "SELECT typeof(value) FROM data WHERE topic = '/photonvision/rawBytes' LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(storage, "blob");
+
+        let session = database.current_session();
+        let at = |timestamp| {
+            database
+                .get_value_at(session, "/photonvision/rawBytes", None, timestamp)
+                .unwrap()
+                .map(|entree| entree.value)
+        };
+        assert_eq!(at(10), Some(TableValue::Raw(bytes.clone())));
+        assert_eq!(at(15), Some(TableValue::Raw(bytes)));
+        assert_eq!(at(25), Some(TableValue::Raw(vec![1])));
+        assert_eq!(at(5), None);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_wall_time_values() {
+        let mut database = utils::get_database(2);
+        let wall_entree = |timestamp: u64, wall_time: u64| {
+            let mut entree = TableEntree::new("/Clock".to_string(), TableValue::Int(1), timestamp);
+            entree.wall_time = Some(wall_time);
+            entree
+        };
+
+        database.add_value(wall_entree(900, 1_000)).unwrap();
+        database
+            .add_value(TableEntree::new(
+                "/Clock".to_string(),
+                TableValue::Int(1),
+                950,
+            ))
+            .unwrap();
+        database.start_session().unwrap();
+        // the robot rebooted, its time starts over but the wall clock keeps going
+        database.add_value(wall_entree(10, 2_000)).unwrap();
+        database.add_value(wall_entree(20, 2_010)).unwrap();
+
+        assert_eq!(
+            database
+                .get_wall_time_values("/Clock", None, 0, 10)
+                .unwrap(),
+            vec![
+                wall_entree(20, 2_010),
+                wall_entree(10, 2_000),
+                wall_entree(900, 1_000)
+            ]
+        );
+        assert_eq!(
+            database
+                .get_wall_time_values("/Clock", None, 2_005, 10)
+                .unwrap(),
+            vec![wall_entree(20, 2_010)]
+        );
+        assert_eq!(
+            database
+                .get_session_values(database.current_session(), "/Clock", None, u64::MAX, 1)
+                .unwrap(),
+            vec![wall_entree(20, 2_010)]
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_sources() {
+        let mut database = utils::get_database(2);
+        let sourced = |source: &str, value: f64, timestamp: u64| {
+            let mut entree = TableEntree::new(
+                "/Drive/Speed".to_string(),
+                TableValue::Double(value),
+                timestamp,
+            );
+            entree.source = Some(source.to_string());
+            entree
+        };
+        // the robot has been on for longer than the simulator, its clock is ahead
+        database
+            .add_values(&[
+                sourced("robot", 1.0, 1_000),
+                sourced("sim", 2.0, 10),
+                sourced("robot", 1.5, 1_010),
+                sourced("sim", 2.5, 20),
+            ])
+            .unwrap();
+
+        let session = database.current_session();
+        assert_eq!(
+            database
+                .get_session_values(session, "/Drive/Speed", Some("robot"), u64::MAX, 5)
+                .unwrap(),
+            vec![sourced("robot", 1.5, 1_010), sourced("robot", 1.0, 1_000)]
+        );
+        // without a source the connection that updated the topic last is read, on its own clock
+        assert_eq!(
+            database.get_value("/Drive/Speed").unwrap(),
+            sourced("sim", 2.5, 20)
+        );
+        assert_eq!(
+            database
+                .get_session_values(session, "/Drive/Speed", None, 5, 5)
+                .unwrap(),
+            vec![sourced("sim", 2.5, 20)]
+        );
+        let summaries = database
+            .get_topic_summaries(session, "", Some("robot"))
+            .unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].latest, sourced("robot", 1.5, 1_010));
+        let summaries = database.get_topic_summaries(session, "", None).unwrap();
+        assert_eq!(summaries[0].latest, sourced("sim", 2.5, 20));
+
+        // every connection has its own row in the catalog
+        let topics = database.get_topics("/Drive/Speed").unwrap();
+        assert_eq!(
+            topics
+                .iter()
+                .map(|topic| (topic.source.clone().unwrap(), topic.rows))
+                .collect::<Vec<_>>(),
+            vec![("robot".to_string(), 2), ("sim".to_string(), 2)]
+        );
+
+        // cleaning keeps the newest entries of every connection
+        database.clean_database_time(5).unwrap();
+        assert_eq!(database.length().unwrap(), 2);
+        assert_eq!(
+            database
+                .get_session_values(session, "/Drive/Speed", Some("robot"), u64::MAX, 5)
+                .unwrap(),
+            vec![sourced("robot", 1.5, 1_010)]
+        );
+
+        // losing one connection only unannounces its own topics
+        for (name, source) in [("/Robot/kP", "robot"), ("/Sim/kP", "sim")] {
+            let mut topic = TopicInfo::from_nt3(name.to_string(), "double".to_string(), 1, false);
+            topic.source = Some(source.to_string());
+            database.announce_topic(&topic).unwrap();
+        }
+        database.unannounce_topic(None, Some("sim")).unwrap();
+        let announced: Vec<(String, Option<String>)> = database
+            .get_topics("")
+            .unwrap()
+            .into_iter()
+            .filter(|topic| topic.announced)
+            .map(|topic| (topic.name, topic.source))
+            .collect();
+        assert_eq!(
+            announced,
+            vec![("/Robot/kP".to_string(), Some("robot".to_string()))]
+        );
+    }
 }
@@ -1 +1,6 @@
+pub mod session;
 pub mod table_entree;
+pub mod table_value;
+pub mod topic_info;
+pub mod topic_tree;
+pub mod write_record;
@@ -0,0 +1,18 @@
+///
+/// # Function
+/// A recording session. A new one is started every time the backend starts and every time the bridge (re)connects to the
+/// network table, so data from different matches / robot reboots does not get mixed together.
+///
+/// # Fields
+/// - `id`: The id that the entries in the `data` table reference
+/// - `start_time`: Wall clock time (UNIX microseconds) of when the session was started
+/// - `end_time`: Wall clock time (UNIX microseconds) of when the session was ended. `None` if it is still recording
+/// - `entries`: The amount of entries stored in this session
+///
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct Session {
+    pub id: i64,
+    pub start_time: u64,
+    pub end_time: Option<u64>,
+    pub entries: u32,
+}
@@ -1,26 +1,110 @@
-use network_tables::v4::MessageData;
+use network_tables::{v4::Type, Value};
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+use super::table_value::TableValue;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
 pub struct TableEntree {
     pub topic: String,
-    pub value: String,
-    pub timestamp: u32,
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub value: TableValue,
+    /// Microseconds
+    pub timestamp: u64,
+    /// The fields of a WPILib struct topic (see `StructDecoder`), `value` still has the raw bytes. Left out of the JSON if the topic is not a struct
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<serde_json::Value>,
+    /// The wall clock time of the entry (UNIX microseconds): the robot time moved to the clock of the backend, or the time the
+    /// entry was received if the source has no clock sync. Unlike `timestamp` it keeps going up across robot reboots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_time: Option<u64>,
+    /// The name of the network table connection the entry came from. Left out of the JSON for sources without a name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 impl TableEntree {
-    pub fn new(topic: String, value: String, timestamp: u32) -> Self {
+    pub fn new(topic: String, value: TableValue, timestamp: u64) -> Self {
+        Self {
+            topic,
+            value_type: value.type_name().to_string(),
+            value,
+            timestamp,
+            decoded: None,
+            wall_time: None,
+            source: None,
+        }
+    }
+
+    pub fn with_type(topic: String, value_type: String, value: TableValue, timestamp: u64) -> Self {
         Self {
             topic,
+            value_type,
             value,
             timestamp,
+            decoded: None,
+            wall_time: None,
+            source: None,
         }
     }
 
-    pub fn from_message(data: MessageData) -> Self {
-        Self::new(data.topic_name, data.data.to_string(), data.timestamp)
+    ///
+    /// # Function
+    /// Makes an entree out of a MessagePack value from the network table. If the data does not match the type that the server
+    /// announced, it is stored as its string representation with the type `string` so nothing is lost and it can be read back.
+    ///
+    /// # Parameters
+    /// - `topic`: The topic of the value
+    /// - `value_type`: The type string of the topic (e.g. `struct:Pose2d`, which `r#type` can not hold)
+    /// - `r#type`: The NT4 type the value is read as
+    /// - `value`: The MessagePack value
+    /// - `timestamp`: The time of the value in microseconds
+    ///
+    pub fn from_value(
+        topic: String,
+        value_type: String,
+        r#type: Type,
+        value: &Value,
+        timestamp: u64,
+    ) -> Self {
+        match TableValue::from_message(r#type, value) {
+            Some(converted) => Self::with_type(topic, value_type, converted, timestamp),
+            None => Self::new(topic, TableValue::String(value.to_string()), timestamp),
+        }
     }
 
     pub fn get_error() -> Self {
-        Self::new("ERROR".to_string(), "ERROR".to_string(), u32::MIN)
+        Self::new(
+            "ERROR".to_string(),
+            TableValue::String("ERROR".to_string()),
+            u64::MIN,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_value_mismatched_type() {
+        let entree = TableEntree::from_value(
+            "/Drive/Pose".to_string(),
+            "double[]".to_string(),
+            Type::DoubleArray,
+            &Value::from("abc"),
+            1,
+        );
+        assert_eq!(entree.value_type, "string");
+        assert_eq!(entree.value, TableValue::String("\"abc\"".to_string()));
+
+        let entree = TableEntree::from_value(
+            "/Drive/Pose".to_string(),
+            "double[]".to_string(),
+            Type::DoubleArray,
+            &Value::Array(vec![Value::F64(1.0)]),
+            1,
+        );
+        assert_eq!(entree.value_type, "double[]");
+        assert_eq!(entree.value, TableValue::DoubleArray(vec![1.0]));
     }
 }
@@ -0,0 +1,328 @@
+use network_tables::{v4::Type, Value};
+use rusqlite::types::{FromSqlError, ToSql, ToSqlOutput, Value as SqlValue, ValueRef};
+
+///
+/// # Function
+/// A typed NetworkTables value. This is what gets stored in the `value` column of the database and what the API sends back
+/// to the frontend, so numbers come back as JSON numbers, booleans as JSON booleans and arrays as JSON arrays.
+///
+/// # Serialization
+/// The enum is `untagged` which means that only the inner value is written to JSON (e.g. `1.5` instead of `{"Double": 1.5}`).
+/// The NT4 type string that goes with it is stored next to it in `TableEntree::type`.
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum TableValue {
+    Boolean(bool),
+    Int(i64),
+    Double(f64),
+    Float(f32),
+    String(String),
+    BooleanArray(Vec<bool>),
+    IntArray(Vec<i64>),
+    DoubleArray(Vec<f64>),
+    FloatArray(Vec<f32>),
+    StringArray(Vec<String>),
+    Raw(Vec<u8>),
+}
+
+impl TableValue {
+    ///
+    /// # Function
+    /// Converts a raw MessagePack value coming from the network table into a typed value based on the NT4 type of the topic.
+    ///
+    /// # Parameters
+    /// - `r#type`: The NT4 type that came with the message
+    /// - `value`: The MessagePack value that came with the message
+    ///
+    /// # Returns
+    /// `None` if the data does not match the type that the server announced (see `TableEntree::from_value`)
+    ///
+    pub fn from_message(r#type: Type, value: &Value) -> Option<Self> {
+        match r#type {
+            Type::Boolean => value.as_bool().map(TableValue::Boolean),
+            Type::Int => as_int(value).map(TableValue::Int),
+            Type::Double => value.as_f64().map(TableValue::Double),
+            Type::Float => value.as_f64().map(|v| TableValue::Float(v as f32)),
+            Type::String | Type::Json => value.as_str().map(|v| TableValue::String(v.to_string())),
+            Type::Raw | Type::Rpc | Type::MsgPack | Type::ProtoBuf => {
+                value.as_slice().map(|v| TableValue::Raw(v.to_vec()))
+            }
+            Type::BooleanArray => as_array(value, Value::as_bool).map(TableValue::BooleanArray),
+            Type::IntArray => as_array(value, as_int).map(TableValue::IntArray),
+            Type::DoubleArray => as_array(value, Value::as_f64).map(TableValue::DoubleArray),
+            Type::FloatArray => {
+                as_array(value, |v| v.as_f64().map(|v| v as f32)).map(TableValue::FloatArray)
+            }
+            Type::StringArray => {
+                as_array(value, |v| v.as_str().map(str::to_string)).map(TableValue::StringArray)
+            }
+        }
+    }
+
+    ///
+    /// # Function
+    /// Reads a value that was sent to the API as JSON (e.g. a value the dashboard wants to write to the network table).
+    /// Unlike deserializing a `TableValue` directly, the NT4 type decides what the JSON is read as, so `1` can be a double.
+    ///
+    /// # Parameters
+    /// - `r#type`: The NT4 type the value should have
+    /// - `value`: The JSON value
+    ///
+    /// # Returns
+    /// `None` if the JSON does not fit the type
+    ///
+    pub fn from_json(r#type: Type, value: &serde_json::Value) -> Option<Self> {
+        fn array<T: serde::de::DeserializeOwned>(value: &serde_json::Value) -> Option<Vec<T>> {
+            serde_json::from_value(value.clone()).ok()
+        }
+
+        match r#type {
+            Type::Boolean => value.as_bool().map(TableValue::Boolean),
+            Type::Int => value.as_i64().map(TableValue::Int),
+            Type::Double => value.as_f64().map(TableValue::Double),
+            Type::Float => value.as_f64().map(|v| TableValue::Float(v as f32)),
+            Type::String | Type::Json => value.as_str().map(|v| TableValue::String(v.to_string())),
+            Type::Raw | Type::Rpc | Type::MsgPack | Type::ProtoBuf => {
+                array(value).map(TableValue::Raw)
+            }
+            Type::BooleanArray => array(value).map(TableValue::BooleanArray),
+            Type::IntArray => array(value).map(TableValue::IntArray),
+            Type::DoubleArray => array(value).map(TableValue::DoubleArray),
+            Type::FloatArray => array(value).map(TableValue::FloatArray),
+            Type::StringArray => array(value).map(TableValue::StringArray),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Converts the value to the MessagePack value that is sent to the network table. This is the opposite of `from_message`.
+    ///
+    pub fn to_message(&self) -> Value {
+        fn array<T: Clone + Into<Value>>(values: &[T]) -> Value {
+            Value::Array(values.iter().cloned().map(Into::into).collect())
+        }
+
+        match self {
+            TableValue::Boolean(v) => Value::Boolean(*v),
+            TableValue::Int(v) => Value::from(*v),
+            TableValue::Double(v) => Value::F64(*v),
+            TableValue::Float(v) => Value::F32(*v),
+            TableValue::String(v) => Value::from(v.as_str()),
+            TableValue::BooleanArray(v) => array(v),
+            TableValue::IntArray(v) => array(v),
+            TableValue::DoubleArray(v) => array(v),
+            TableValue::FloatArray(v) => array(v),
+            TableValue::StringArray(v) => array(v),
+            TableValue::Raw(v) => Value::Binary(v.clone()),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Reads a value back out of the database. The NT4 type string is needed because SQLite only knows about integers, reals, text and blobs.
+    /// Text that does not fit the type (e.g. an array that is not valid JSON) is read as a string, so one bad row can not break the topic.
+    ///
+    /// # Parameters
+    /// - `value_type`: The NT4 type string stored in the `type` column
+    /// - `value`: The raw SQLite value stored in the `value` column
+    ///
+    pub fn from_sql(value_type: &str, value: SqlValue) -> Result<Self, FromSqlError> {
+        match (value_type, value) {
+            ("boolean", SqlValue::Integer(v)) => Ok(TableValue::Boolean(v != 0)),
+            ("int", SqlValue::Integer(v)) => Ok(TableValue::Int(v)),
+            ("double", SqlValue::Real(v)) => Ok(TableValue::Double(v)),
+            ("double", SqlValue::Integer(v)) => Ok(TableValue::Double(v as f64)),
+            ("float", SqlValue::Real(v)) => Ok(TableValue::Float(v as f32)),
+            ("float", SqlValue::Integer(v)) => Ok(TableValue::Float(v as f32)),
+            ("boolean[]", SqlValue::Text(v)) => Ok(from_json(v, TableValue::BooleanArray)),
+            ("int[]", SqlValue::Text(v)) => Ok(from_json(v, TableValue::IntArray)),
+            ("double[]", SqlValue::Text(v)) => Ok(from_json(v, TableValue::DoubleArray)),
+            ("float[]", SqlValue::Text(v)) => Ok(from_json(v, TableValue::FloatArray)),
+            ("string[]", SqlValue::Text(v)) => Ok(from_json(v, TableValue::StringArray)),
+            (_, SqlValue::Text(v)) => Ok(TableValue::String(v)),
+            (_, SqlValue::Blob(v)) => Ok(TableValue::Raw(v)),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Gets the NT4 type string that this value would have if it was published to the network table.
+    ///
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TableValue::Boolean(_) => "boolean",
+            TableValue::Int(_) => "int",
+            TableValue::Double(_) => "double",
+            TableValue::Float(_) => "float",
+            TableValue::String(_) => "string",
+            TableValue::BooleanArray(_) => "boolean[]",
+            TableValue::IntArray(_) => "int[]",
+            TableValue::DoubleArray(_) => "double[]",
+            TableValue::FloatArray(_) => "float[]",
+            TableValue::StringArray(_) => "string[]",
+            TableValue::Raw(_) => "raw",
+        }
+    }
+}
+
+///
+/// # Function
+/// Scalars are stored as native SQLite values so that numeric queries (`AVG(value)`, `value > 12.0`, ...) work.
+/// Arrays are stored as JSON text and raw bytes as a blob.
+///
+impl ToSql for TableValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            TableValue::Boolean(v) => ToSqlOutput::from(*v),
+            TableValue::Int(v) => ToSqlOutput::from(*v),
+            TableValue::Double(v) => ToSqlOutput::from(*v),
+            TableValue::Float(v) => ToSqlOutput::from(*v as f64),
+            TableValue::String(v) => ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes())),
+            TableValue::Raw(v) => ToSqlOutput::Borrowed(ValueRef::Blob(v)),
+            TableValue::BooleanArray(_)
+            | TableValue::IntArray(_)
+            | TableValue::DoubleArray(_)
+            | TableValue::FloatArray(_)
+            | TableValue::StringArray(_) => ToSqlOutput::from(
+                serde_json::to_string(self)
+                    .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?,
+            ),
+        })
+    }
+}
+
+fn as_int(value: &Value) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_u64().map(|v| v as i64))
+}
+
+fn as_array<T>(value: &Value, convert: impl Fn(&Value) -> Option<T>) -> Option<Vec<T>> {
+    value.as_array()?.iter().map(convert).collect()
+}
+
+/// Reads an array that is stored as JSON, or the text itself as a string if it is not one
+fn from_json<T: serde::de::DeserializeOwned>(
+    value: String,
+    array: impl FnOnce(T) -> TableValue,
+) -> TableValue {
+    match serde_json::from_str(&value) {
+        Ok(values) => array(values),
+        Err(_) => TableValue::String(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_message_scalars() {
+        assert_eq!(
+            TableValue::from_message(Type::Double, &Value::F64(1.5)),
+            Some(TableValue::Double(1.5))
+        );
+        assert_eq!(
+            TableValue::from_message(Type::Boolean, &Value::Boolean(true)),
+            Some(TableValue::Boolean(true))
+        );
+        assert_eq!(
+            TableValue::from_message(Type::Int, &Value::from(-3)),
+            Some(TableValue::Int(-3))
+        );
+        assert_eq!(
+            TableValue::from_message(Type::String, &Value::from("abc")),
+            Some(TableValue::String("abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_from_message_arrays_and_raw() {
+        assert_eq!(
+            TableValue::from_message(
+                Type::DoubleArray,
+                &Value::Array(vec![Value::F64(1.0), Value::F64(2.5)])
+            ),
+            Some(TableValue::DoubleArray(vec![1.0, 2.5]))
+        );
+        assert_eq!(
+            TableValue::from_message(Type::Raw, &Value::Binary(vec![1, 2, 3])),
+            Some(TableValue::Raw(vec![1, 2, 3]))
+        );
+    }
+
+    #[test]
+    fn test_from_message_mismatched_type() {
+        assert_eq!(
+            TableValue::from_message(Type::Double, &Value::from("abc")),
+            None
+        );
+        assert_eq!(
+            TableValue::from_message(Type::DoubleArray, &Value::from(1.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_sql_mismatched_text() {
+        assert_eq!(
+            TableValue::from_sql("double[]", SqlValue::Text("[1.0,2.5]".to_string())).unwrap(),
+            TableValue::DoubleArray(vec![1.0, 2.5])
+        );
+        assert_eq!(
+            TableValue::from_sql("double[]", SqlValue::Text("1.5".to_string())).unwrap(),
+            TableValue::String("1.5".to_string())
+        );
+        assert_eq!(
+            TableValue::from_sql("double", SqlValue::Text("\"abc\"".to_string())).unwrap(),
+            TableValue::String("\"abc\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_json_and_message_round_trip() {
+        let value = TableValue::from_json(Type::Double, &serde_json::json!(1)).unwrap();
+        assert_eq!(value, TableValue::Double(1.0));
+        assert_eq!(
+            TableValue::from_message(Type::Double, &value.to_message()),
+            Some(value)
+        );
+
+        let value =
+            TableValue::from_json(Type::StringArray, &serde_json::json!(["a", "b"])).unwrap();
+        assert_eq!(
+            TableValue::from_message(Type::StringArray, &value.to_message()),
+            Some(value)
+        );
+
+        assert_eq!(
+            TableValue::from_json(Type::Boolean, &serde_json::json!("true")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_only_blobs_are_raw() {
+        // a payload that did not fit a binary type was stored as text, it is not made into bytes
+        assert_eq!(
+            TableValue::from_sql("raw", SqlValue::Text("ab".to_string())).unwrap(),
+            TableValue::String("ab".to_string())
+        );
+        assert_eq!(
+            TableValue::from_sql("struct:Pose2d", SqlValue::Blob(vec![1, 2])).unwrap(),
+            TableValue::Raw(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_serialize_untagged() {
+        assert_eq!(
+            serde_json::to_string(&TableValue::IntArray(vec![1, 2])).unwrap(),
+            "[1,2]"
+        );
+        assert_eq!(
+            serde_json::to_string(&TableValue::Boolean(false)).unwrap(),
+            "false"
+        );
+    }
+}
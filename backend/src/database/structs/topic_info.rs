@@ -0,0 +1,121 @@
+use crate::database::now_micros;
+
+///
+/// # Function
+/// An entry of the topic catalog. It is made from the NT4 announcement of the topic (or from the first value, if the topic
+/// was never announced) and kept up to date by the bridge.
+///
+/// # Fields
+/// - `name`: The topic name
+/// - `value_type`: The NT4 type string (written as `type` in JSON)
+/// - `id`: The id the server gave the topic, `None` if it is not announced right now
+/// - `properties`: All the properties from the announcement, as a JSON object
+/// - `persistent`, `retained`, `cached`: The standard NT4 properties (`cached` is `true` unless the server says otherwise)
+/// - `announced`: If the topic is announced by the server right now
+/// - `first_seen`, `last_seen`: Wall clock times (UNIX microseconds) of the first and last announcement or value
+/// - `rows`: The amount of entries stored for this topic by its connection (in all sessions)
+/// - `source`: The name of the network table connection the topic is from. Left out of the JSON if it has none
+///
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct TopicInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub id: Option<i32>,
+    pub properties: serde_json::Value,
+    pub persistent: bool,
+    pub retained: bool,
+    pub cached: bool,
+    pub announced: bool,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub rows: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl TopicInfo {
+    ///
+    /// # Function
+    /// Makes a catalog entry from an NT4 announcement (of the robot, or of a topic a client published to the embedded server).
+    ///
+    /// # Parameters
+    /// - `name`: The topic name
+    /// - `value_type`: The NT4 type string
+    /// - `id`: The id of the topic
+    /// - `properties`: The properties of the topic, as a JSON object
+    ///
+    pub fn from_properties(
+        name: String,
+        value_type: String,
+        id: i32,
+        properties: serde_json::Value,
+    ) -> Self {
+        let flag = |name: &str, default: bool| {
+            properties
+                .get(name)
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(default)
+        };
+
+        Self {
+            name,
+            value_type,
+            id: Some(id),
+            persistent: flag("persistent", false),
+            retained: flag("retained", false),
+            cached: flag("cached", true),
+            properties,
+            announced: true,
+            first_seen: now_micros(),
+            last_seen: now_micros(),
+            rows: 0,
+            source: None,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Makes a catalog entry from an NT3 entry. NT3 has no properties, only a persistent flag, so that is the only property.
+    ///
+    pub fn from_nt3(name: String, value_type: String, id: u16, persistent: bool) -> Self {
+        Self {
+            name,
+            value_type,
+            id: Some(id as i32),
+            properties: serde_json::json!({ "persistent": persistent }),
+            persistent,
+            retained: false,
+            cached: true,
+            announced: true,
+            first_seen: now_micros(),
+            last_seen: now_micros(),
+            rows: 0,
+            source: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_properties() {
+        let info = TopicInfo::from_properties(
+            "/Tuning/kP".to_string(),
+            "double".to_string(),
+            7,
+            serde_json::json!({"persistent": true, "cached": false}),
+        );
+        assert_eq!(info.value_type, "double");
+        assert_eq!(info.id, Some(7));
+        assert!(info.persistent);
+        assert!(!info.retained);
+        assert!(!info.cached);
+        assert_eq!(
+            info.properties,
+            serde_json::json!({"persistent": true, "cached": false})
+        );
+    }
+}
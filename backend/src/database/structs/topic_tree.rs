@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use super::table_entree::TableEntree;
+
+///
+/// # Function
+/// The newest entry of a topic and how often it is updated. This is what the topic tree is built from.
+///
+/// # Fields
+/// - `latest`: The newest entry of the topic
+/// - `update_rate`: The updates per second over the last seconds of the session (`0.0` if it was not updated in that time)
+///
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct TopicSummary {
+    pub latest: TableEntree,
+    pub update_rate: f64,
+}
+
+///
+/// # Function
+/// A node of the topic tree. Topics are split on `/`, so `/SmartDashboard/Drive/Speed` is the `Speed` node in the `Drive`
+/// node in the `SmartDashboard` node. A node can be a table, a topic or both (NT4 allows `/Arm` and `/Arm/Angle`).
+///
+/// # Fields
+/// - `name`: The last part of the path (`""` for the root)
+/// - `path`: The full path of the node, always starting with `/` (e.g. `/SmartDashboard/Drive`)
+/// - `latest`: The newest entry if the node is a topic, `null` if it is only a table. `latest.topic` is the exact topic string
+/// - `update_rate`: The updates per second if the node is a topic
+/// - `topic_count`: The amount of topics in this node and everything below it
+/// - `has_children`: If the node has child nodes, even if they were not expanded
+/// - `children`: The child nodes sorted by name. Empty if the node was not expanded
+///
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct TopicNode {
+    pub name: String,
+    pub path: String,
+    pub latest: Option<TableEntree>,
+    pub update_rate: Option<f64>,
+    pub topic_count: u64,
+    pub has_children: bool,
+    pub children: Vec<TopicNode>,
+}
+
+#[derive(Debug, Default)]
+struct Branch {
+    summary: Option<TopicSummary>,
+    children: BTreeMap<String, Branch>,
+}
+
+impl Branch {
+    fn topic_count(&self) -> u64 {
+        self.summary.is_some() as u64 + self.children.values().map(Branch::topic_count).sum::<u64>()
+    }
+
+    fn into_node(self, name: String, path: String, depth: Option<u32>) -> TopicNode {
+        let topic_count = self.topic_count();
+        let has_children = !self.children.is_empty();
+        let children = match depth {
+            Some(0) => Vec::new(),
+            _ => self
+                .children
+                .into_iter()
+                .map(|(child_name, child)| {
+                    let child_path = format!("{}/{}", path.trim_end_matches('/'), child_name);
+                    child.into_node(child_name, child_path, depth.map(|depth| depth - 1))
+                })
+                .collect(),
+        };
+
+        TopicNode {
+            name,
+            update_rate: self.summary.as_ref().map(|summary| summary.update_rate),
+            latest: self.summary.map(|summary| summary.latest),
+            path,
+            topic_count,
+            has_children,
+            children,
+        }
+    }
+}
+
+///
+/// # Function
+/// Splits a topic or a path into its parts. Empty parts (leading, trailing or double `/`) are skipped.
+///
+pub fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+impl TopicNode {
+    ///
+    /// # Function
+    /// Builds the tree below `path` out of topic summaries. Topics that are not below `path` are left out.
+    ///
+    /// # Parameters
+    /// - `summaries`: The topics
+    /// - `path`: The node to build the tree from (`/` for the root)
+    /// - `depth`: How many levels of children to expand. `Some(1)` only gets the direct children (for lazy loading), `None` gets everything
+    ///
+    pub fn build(summaries: Vec<TopicSummary>, path: &str, depth: Option<u32>) -> Self {
+        let base = path_segments(path);
+        let mut root = Branch::default();
+
+        for summary in summaries {
+            let topic = summary.latest.topic.clone();
+            let segments = path_segments(&topic);
+            if !segments.starts_with(&base) {
+                continue;
+            }
+
+            let branch = segments[base.len()..]
+                .iter()
+                .fold(&mut root, |branch, segment| {
+                    branch.children.entry(segment.to_string()).or_default()
+                });
+            branch.summary = Some(summary);
+        }
+
+        root.into_node(
+            base.last().unwrap_or(&"").to_string(),
+            format!("/{}", base.join("/")),
+            depth,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::structs::table_value::TableValue;
+
+    use super::*;
+
+    fn summary(topic: &str) -> TopicSummary {
+        TopicSummary {
+            latest: TableEntree::new(topic.to_string(), TableValue::Double(1.0), 5),
+            update_rate: 50.0,
+        }
+    }
+
+    fn summaries() -> Vec<TopicSummary> {
+        vec![
+            summary("/SmartDashboard/Drive/Speed"),
+            summary("/SmartDashboard/Drive/Heading"),
+            summary("/SmartDashboard/Arm"),
+            summary("/SmartDashboard/Arm/Angle"),
+            summary("/FMSInfo/MatchNumber"),
+        ]
+    }
+
+    #[test]
+    fn test_full_tree() {
+        let root = TopicNode::build(summaries(), "/", None);
+        assert_eq!(root.path, "/");
+        assert_eq!(root.topic_count, 5);
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].name, "FMSInfo");
+
+        let smart_dashboard = &root.children[1];
+        assert_eq!(smart_dashboard.path, "/SmartDashboard");
+        assert_eq!(smart_dashboard.latest, None);
+
+        // `/SmartDashboard/Arm` is a topic and a table
+        let arm = &smart_dashboard.children[0];
+        assert_eq!(arm.latest.as_ref().unwrap().topic, "/SmartDashboard/Arm");
+        assert_eq!(arm.update_rate, Some(50.0));
+        assert_eq!(arm.topic_count, 2);
+        assert_eq!(arm.children[0].path, "/SmartDashboard/Arm/Angle");
+
+        let drive = &smart_dashboard.children[1];
+        assert_eq!(
+            drive
+                .children
+                .iter()
+                .map(|child| child.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Heading", "Speed"]
+        );
+    }
+
+    #[test]
+    fn test_lazy_expansion() {
+        let node = TopicNode::build(summaries(), "/SmartDashboard/", Some(1));
+        assert_eq!(node.name, "SmartDashboard");
+        assert_eq!(node.path, "/SmartDashboard");
+        assert_eq!(node.topic_count, 4);
+        assert_eq!(node.children.len(), 2);
+        assert!(node.children[1].has_children);
+        assert!(node.children[1].children.is_empty());
+        assert_eq!(node.children[1].topic_count, 2);
+
+        let missing = TopicNode::build(summaries(), "/Nothing", None);
+        assert_eq!(missing.topic_count, 0);
+        assert!(!missing.has_children);
+    }
+}
@@ -0,0 +1,27 @@
+use super::table_value::TableValue;
+
+///
+/// # Function
+/// An entry of the audit log of values that were written to the network table from the dashboard. Writes that were refused
+/// or failed are recorded too.
+///
+/// # Fields
+/// - `time`: Wall clock time (UNIX microseconds) of the write
+/// - `topic`: The topic that was written to
+/// - `value_type`: The NT4 type of the value (written as `type` in JSON)
+/// - `value`: The value that was written
+/// - `origin`: Where the write came from (`http` or `websocket`)
+/// - `client`: The address of the client that asked for the write, if it is known
+/// - `error`: Why the write did not happen. `None` if it was published
+///
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq)]
+pub struct WriteRecord {
+    pub time: u64,
+    pub topic: String,
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub value: TableValue,
+    pub origin: String,
+    pub client: Option<String>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,710 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use array_elements::ArrayElements;
+use deduplicator::{DeduplicationConfig, Deduplicator};
+use proto_decoder::ProtoDecoder;
+use struct_decoder::StructDecoder;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{
+    database::{
+        now_micros,
+        structs::{table_entree::TableEntree, topic_info::TopicInfo},
+        SQLiteDatabase,
+    },
+    live::LiveFeed,
+};
+
+pub mod array_elements;
+pub mod deduplicator;
+pub mod proto_decoder;
+pub mod struct_decoder;
+
+///
+/// # Function
+/// Settings for the ingestion pipeline.
+///
+/// # Fields
+/// - `channel_capacity`: How many entries can wait in the queue before new ones are dropped
+/// - `max_batch_size`: The writer flushes as soon as this many entries are waiting
+/// - `max_batch_delay`: The writer flushes at least this often (milliseconds) if there is anything waiting
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IngestConfig {
+    pub channel_capacity: usize,
+    pub max_batch_size: usize,
+    pub max_batch_delay: u64,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            channel_capacity: 10_000,
+            max_batch_size: 500,
+            max_batch_delay: 100,
+        }
+    }
+}
+
+///
+/// # Function
+/// Counters that describe how the ingestion pipeline is doing. They are shared between the bridge, the writer task and the server.
+///
+/// # Fields
+/// - `queued`: Entries that were put in the queue
+/// - `flushed`: Entries that were written to the database (array elements that are recorded as their own topics count too)
+/// - `dropped`: Entries that were thrown away, either because the queue was full or because the database write failed
+/// - `deduplicated`: Entries that were not written because they did not change (only with change-only recording)
+///
+#[derive(Debug, Default)]
+pub struct IngestCounters {
+    queued: AtomicU64,
+    flushed: AtomicU64,
+    dropped: AtomicU64,
+    deduplicated: AtomicU64,
+}
+
+///
+/// # Function
+/// A snapshot of `IngestCounters` that can be sent back as JSON.
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IngestStats {
+    pub queued: u64,
+    pub flushed: u64,
+    pub dropped: u64,
+    pub deduplicated: u64,
+}
+
+impl IngestCounters {
+    pub fn stats(&self) -> IngestStats {
+        IngestStats {
+            queued: self.queued.load(Ordering::Relaxed),
+            flushed: self.flushed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            deduplicated: self.deduplicated.load(Ordering::Relaxed),
+        }
+    }
+}
+
+///
+/// # Function
+/// What can be sent to the writer task. Session changes go through the same queue as the entries so entries that were
+/// received before a reconnect still end up in the session they belong to. Topic announcements go through it so the
+/// database is only ever written by the writer task.
+///
+#[derive(Debug)]
+enum IngestCommand {
+    Entree(TableEntree),
+    StartSession,
+    EndSession,
+    Announce(TopicInfo),
+    Unannounce(Option<String>, Option<String>),
+    Stop,
+}
+
+///
+/// # Function
+/// The sending side of the ingestion pipeline. It is cheap to clone so every source of data can have one.
+/// Everything that is pushed is also published to the live feed. WPILib struct and protobuf topics are decoded before that,
+/// so the live clients and the database both get the decoded fields.
+///
+#[derive(Debug, Clone)]
+pub struct Ingest {
+    sender: mpsc::Sender<IngestCommand>,
+    counters: Arc<IngestCounters>,
+    live: LiveFeed,
+    structs: Arc<Mutex<StructDecoder>>,
+    protos: Arc<Mutex<ProtoDecoder>>,
+}
+
+///
+/// # Function
+/// The receiving side of the ingestion pipeline. `run` has to be spawned as a task for anything to be written to the database.
+///
+#[derive(Debug)]
+pub struct IngestWriter {
+    receiver: mpsc::Receiver<IngestCommand>,
+    counters: Arc<IngestCounters>,
+    config: IngestConfig,
+    deduplicator: Option<Deduplicator>,
+    array_elements: Option<ArrayElements>,
+}
+
+impl Ingest {
+    ///
+    /// # Function
+    /// Makes a new ingestion pipeline.
+    ///
+    /// # Parameters
+    /// - `config`: The queue and batch settings
+    /// - `live`: The feed that every pushed entry is streamed to
+    ///
+    /// # Returns
+    /// The `Ingest` handle that data is pushed into and the `IngestWriter` that writes it to the database
+    ///
+    pub fn new(config: IngestConfig, live: LiveFeed) -> (Ingest, IngestWriter) {
+        let (sender, receiver) = mpsc::channel(config.channel_capacity.max(1));
+        let counters = Arc::new(IngestCounters::default());
+
+        (
+            Ingest {
+                sender,
+                counters: counters.clone(),
+                live,
+                structs: Arc::new(Mutex::new(StructDecoder::default())),
+                protos: Arc::new(Mutex::new(ProtoDecoder::default())),
+            },
+            IngestWriter {
+                receiver,
+                counters,
+                config,
+                deduplicator: None,
+                array_elements: None,
+            },
+        )
+    }
+
+    ///
+    /// # Function
+    /// Puts an entry in the queue. This never waits: if the queue is full the entry is dropped (and counted) so a slow
+    /// database can not stall the network table connection. The entry is sent to the live clients either way.
+    ///
+    /// # Returns
+    /// `true` if the entry was queued
+    ///
+    pub fn push(&self, mut entree: TableEntree) -> bool {
+        // sources without their own clock sync get the time that the entry got here
+        entree.wall_time.get_or_insert_with(now_micros);
+        if let Ok(mut structs) = self.structs.lock() {
+            structs.decode(&mut entree);
+        }
+        if let Ok(mut protos) = self.protos.lock() {
+            protos.decode(&mut entree);
+        }
+        self.live.publish(&entree);
+
+        match self.sender.try_send(IngestCommand::Entree(entree)) {
+            Ok(()) => {
+                self.counters.queued.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => {
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    ///
+    /// # Function
+    /// Starts a new recording session once everything that is already in the queue has been written.
+    ///
+    pub async fn start_session(&self) {
+        let _ = self.sender.send(IngestCommand::StartSession).await;
+    }
+
+    ///
+    /// # Function
+    /// Ends the current recording session once everything that is already in the queue has been written.
+    ///
+    pub async fn end_session(&self) {
+        let _ = self.sender.send(IngestCommand::EndSession).await;
+    }
+
+    ///
+    /// # Function
+    /// Adds (or updates) a topic in the topic catalog. The type of the topic is also used to decode struct and protobuf topics.
+    ///
+    pub async fn announce(&self, topic: TopicInfo) {
+        if let Ok(mut structs) = self.structs.lock() {
+            structs.set_type(topic.source.as_deref(), &topic.name, &topic.value_type);
+        }
+        if let Ok(mut protos) = self.protos.lock() {
+            protos.set_type(topic.source.as_deref(), &topic.name, &topic.value_type);
+        }
+        let _ = self.sender.send(IngestCommand::Announce(topic)).await;
+    }
+
+    ///
+    /// # Function
+    /// Marks a topic of the topic catalog as not announced anymore. `None` marks every topic (used when the connection is lost).
+    /// `source` only marks the topics of that network table connection, `None` marks the topics of every connection.
+    ///
+    pub async fn unannounce(&self, name: Option<String>, source: Option<String>) {
+        let _ = self
+            .sender
+            .send(IngestCommand::Unannounce(name, source))
+            .await;
+    }
+
+    ///
+    /// # Function
+    /// Stops the writer once everything that is already in the queue has been written. Used when the backend shuts down,
+    /// sources can still have a handle then (e.g. in the callbacks of a network table client). Nothing is written afterwards.
+    ///
+    pub async fn stop(&self) {
+        let _ = self.sender.send(IngestCommand::Stop).await;
+    }
+
+    pub fn counters(&self) -> Arc<IngestCounters> {
+        self.counters.clone()
+    }
+}
+
+impl IngestWriter {
+    ///
+    /// # Function
+    /// Turns on change-only recording: entries that did not change from the last recorded value of their topic are not
+    /// written to the database. They are still streamed live.
+    ///
+    /// # Parameters
+    /// - `config`: The deadbands and the keyframe interval
+    ///
+    pub fn with_deduplication(mut self, config: DeduplicationConfig) -> Self {
+        self.deduplicator = Some(Deduplicator::new(config));
+        self
+    }
+
+    ///
+    /// # Function
+    /// Turns on recording the elements of array topics as their own topics (e.g. `/Drive/ModuleAngles[2]`) for the topics
+    /// that `array_elements` matches. Every element goes through change-only recording on its own.
+    ///
+    pub fn with_array_elements(mut self, array_elements: ArrayElements) -> Self {
+        self.array_elements = Some(array_elements);
+        self
+    }
+
+    ///
+    /// # Function
+    /// Writes everything that comes through the queue to the database in batches. A batch is flushed when it reaches
+    /// `max_batch_size`, when `max_batch_delay` has passed, before a session change and when every `Ingest` handle has been dropped.
+    ///
+    /// # Parameters
+    /// - `database`: The database that the entries will be written to
+    ///
+    pub async fn run(mut self, database: Arc<Mutex<SQLiteDatabase>>) {
+        let mut batch: Vec<TableEntree> = Vec::with_capacity(self.config.max_batch_size);
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.config.max_batch_delay.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                command = self.receiver.recv() => match command {
+                    Some(IngestCommand::Entree(entree)) => {
+                        let elements = self
+                            .array_elements
+                            .as_ref()
+                            .map(|array_elements| array_elements.split(&entree))
+                            .unwrap_or_default();
+                        for element in elements {
+                            if self.deduplicator.as_mut().is_none_or(|deduplicator| deduplicator.keep(&element)) {
+                                batch.push(element);
+                            }
+                        }
+
+                        if self.deduplicator.as_mut().is_some_and(|deduplicator| !deduplicator.keep(&entree)) {
+                            self.counters.deduplicated.fetch_add(1, Ordering::Relaxed);
+                            continue;
+                        }
+
+                        batch.push(entree);
+                        if batch.len() >= self.config.max_batch_size {
+                            self.flush(&mut batch, &database);
+                        }
+                    }
+                    Some(IngestCommand::StartSession) => {
+                        self.flush(&mut batch, &database);
+                        if let Some(deduplicator) = self.deduplicator.as_mut() {
+                            deduplicator.reset(); // the first value of every topic is recorded in the new session
+                        }
+                        if let Ok(mut database) = database.lock() {
+                            let _ = database.start_session();
+                        }
+                    }
+                    Some(IngestCommand::EndSession) => {
+                        self.flush(&mut batch, &database);
+                        if let Ok(database) = database.lock() {
+                            let _ = database.end_session();
+                        }
+                    }
+                    Some(IngestCommand::Announce(topic)) => {
+                        if let Ok(database) = database.lock() {
+                            let _ = database.announce_topic(&topic);
+                        }
+                    }
+                    Some(IngestCommand::Unannounce(name, source)) => {
+                        if let Ok(database) = database.lock() {
+                            let _ = database.unannounce_topic(name.as_deref(), source.as_deref());
+                        }
+                    }
+                    Some(IngestCommand::Stop) | None => {
+                        self.flush(&mut batch, &database);
+                        break;
+                    }
+                },
+                _ = interval.tick() => self.flush(&mut batch, &database),
+            }
+        }
+    }
+
+    fn flush(&self, batch: &mut Vec<TableEntree>, database: &Arc<Mutex<SQLiteDatabase>>) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let written = match database.lock() {
+            Ok(mut database) => database.add_values(batch).is_ok(),
+            Err(_) => false,
+        };
+
+        let counter = if written {
+            &self.counters.flushed
+        } else {
+            &self.counters.dropped
+        };
+        counter.fetch_add(batch.len() as u64, Ordering::Relaxed);
+
+        batch.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::structs::table_value::TableValue;
+
+    use super::*;
+
+    fn get_database() -> Arc<Mutex<SQLiteDatabase>> {
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        database.clear_database().unwrap();
+        Arc::new(Mutex::new(database))
+    }
+
+    fn entree(timestamp: u64) -> TableEntree {
+        TableEntree::new("ingest".to_string(), TableValue::Int(1), timestamp)
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_flush_on_batch_size() {
+        let database = get_database();
+        let (ingest, writer) = Ingest::new(
+            IngestConfig {
+                channel_capacity: 100,
+                max_batch_size: 5,
+                max_batch_delay: 60_000,
+            },
+            LiveFeed::default(),
+        );
+        let task = tokio::spawn(writer.run(database.clone()));
+
+        for i in 0..5 {
+            assert!(ingest.push(entree(i)));
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(database.lock().unwrap().topic_length("ingest").unwrap(), 5);
+        assert_eq!(
+            ingest.counters().stats(),
+            IngestStats {
+                queued: 5,
+                flushed: 5,
+                dropped: 0,
+                deduplicated: 0
+            }
+        );
+
+        drop(ingest);
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_flush_on_time_and_close() {
+        let database = get_database();
+        let (ingest, writer) = Ingest::new(
+            IngestConfig {
+                channel_capacity: 100,
+                max_batch_size: 100,
+                max_batch_delay: 10,
+            },
+            LiveFeed::default(),
+        );
+        let task = tokio::spawn(writer.run(database.clone()));
+
+        ingest.push(entree(0));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(database.lock().unwrap().topic_length("ingest").unwrap(), 1);
+
+        ingest.push(entree(1));
+        drop(ingest);
+        task.await.unwrap();
+        assert_eq!(database.lock().unwrap().topic_length("ingest").unwrap(), 2);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_drop_when_full() {
+        let (ingest, _writer) = Ingest::new(
+            IngestConfig {
+                channel_capacity: 2,
+                ..Default::default()
+            },
+            LiveFeed::default(),
+        );
+
+        assert!(ingest.push(entree(0)));
+        assert!(ingest.push(entree(1)));
+        assert!(!ingest.push(entree(2)));
+        assert_eq!(
+            ingest.counters().stats(),
+            IngestStats {
+                queued: 2,
+                flushed: 0,
+                dropped: 1,
+                deduplicated: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_deduplication() {
+        let database = get_database();
+        let (ingest, writer) = Ingest::new(IngestConfig::default(), LiveFeed::default());
+        let task = tokio::spawn(
+            writer
+                .with_deduplication(DeduplicationConfig {
+                    keyframe_interval: 0,
+                    ..Default::default()
+                })
+                .run(database.clone()),
+        );
+
+        for i in 0..5 {
+            ingest.push(entree(i));
+        }
+        ingest.start_session().await;
+        ingest.push(entree(5));
+        drop(ingest);
+        task.await.unwrap();
+
+        // one value for the first session and one for the new one
+        assert_eq!(database.lock().unwrap().topic_length("ingest").unwrap(), 2);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_array_elements() {
+        let database = get_database();
+        let (ingest, writer) = Ingest::new(IngestConfig::default(), LiveFeed::default());
+        let task = tokio::spawn(
+            writer
+                .with_array_elements(ArrayElements::from_list("/Drive/ModuleAngles").unwrap())
+                .with_deduplication(DeduplicationConfig {
+                    keyframe_interval: 0,
+                    ..Default::default()
+                })
+                .run(database.clone()),
+        );
+
+        for (timestamp, angles) in [(1, [0.0, 90.0]), (2, [0.0, 45.0])] {
+            ingest.push(TableEntree::new(
+                "/Drive/ModuleAngles".to_string(),
+                TableValue::DoubleArray(angles.to_vec()),
+                timestamp,
+            ));
+        }
+        ingest.push(TableEntree::new(
+            "/Drive/Speeds".to_string(),
+            TableValue::DoubleArray(vec![1.0]),
+            2,
+        ));
+        drop(ingest);
+        task.await.unwrap();
+
+        let database = database.lock().unwrap();
+        let values = database
+            .get_session_values(
+                database.current_session(),
+                "/Drive/ModuleAngles[1]",
+                None,
+                u64::MAX,
+                5,
+            )
+            .unwrap();
+        let values: Vec<TableValue> = values.into_iter().map(|entree| entree.value).collect();
+        assert_eq!(
+            values,
+            vec![TableValue::Double(45.0), TableValue::Double(90.0)]
+        );
+        // element 0 did not change
+        assert_eq!(database.topic_length("/Drive/ModuleAngles[0]").unwrap(), 1);
+        assert_eq!(database.topic_length("/Drive/ModuleAngles").unwrap(), 2);
+
+        let topics: Vec<String> = database
+            .get_topics("/Drive/")
+            .unwrap()
+            .into_iter()
+            .map(|topic| topic.name)
+            .collect();
+        assert_eq!(
+            topics,
+            vec![
+                "/Drive/ModuleAngles",
+                "/Drive/ModuleAngles[0]",
+                "/Drive/ModuleAngles[1]",
+                "/Drive/Speeds"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_is_streamed_live() {
+        let live = LiveFeed::new(10);
+        let mut subscription = live.subscribe();
+        subscription.set_filter(Some(Default::default()));
+        let (ingest, _writer) = Ingest::new(
+            IngestConfig {
+                channel_capacity: 1,
+                ..Default::default()
+            },
+            live,
+        );
+
+        ingest.push(entree(0));
+        ingest.push(entree(1)); // dropped by the full queue, but still sent live
+
+        for timestamp in 0..2 {
+            let Some(crate::live::LiveMessage::Entree(mut received)) = subscription.recv().await
+            else {
+                panic!("expected an entry");
+            };
+            assert!(received.wall_time.take().is_some());
+            assert_eq!(received, entree(timestamp));
+        }
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_session_change_is_ordered() {
+        let database = get_database();
+        let old_session = database.lock().unwrap().current_session();
+        let (ingest, writer) = Ingest::new(
+            IngestConfig {
+                max_batch_delay: 60_000,
+                ..Default::default()
+            },
+            LiveFeed::default(),
+        );
+        let task = tokio::spawn(writer.run(database.clone()));
+
+        ingest.push(entree(0));
+        ingest.start_session().await;
+        ingest.push(entree(1));
+        drop(ingest);
+        task.await.unwrap();
+
+        let database = database.lock().unwrap();
+        assert_ne!(database.current_session(), old_session);
+        assert_eq!(
+            database
+                .get_session_value(old_session, "ingest", None)
+                .unwrap()
+                .timestamp,
+            0
+        );
+        assert_eq!(database.get_value("ingest").unwrap().timestamp, 1);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_stop_writes_the_queue() {
+        let database = get_database();
+        let session = database.lock().unwrap().current_session();
+        let (ingest, writer) = Ingest::new(
+            IngestConfig {
+                max_batch_delay: 60_000,
+                ..Default::default()
+            },
+            LiveFeed::default(),
+        );
+        let task = tokio::spawn(writer.run(database.clone()));
+
+        ingest.push(entree(0));
+        ingest.end_session().await;
+        ingest.stop().await;
+        // the handle is still there, the writer stops anyway
+        task.await.unwrap();
+
+        let database = database.lock().unwrap();
+        assert_eq!(database.get_value("ingest").unwrap().timestamp, 0);
+        let sessions = database.get_sessions().unwrap();
+        let ended = sessions.iter().find(|s| s.id == session).unwrap();
+        assert!(ended.end_time.is_some());
+        drop(ingest);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_decodes_structs() {
+        let database = get_database();
+        let (ingest, writer) = Ingest::new(IngestConfig::default(), LiveFeed::default());
+        let task = tokio::spawn(writer.run(database.clone()));
+
+        ingest.push(TableEntree::with_type(
+            "/.schema/struct:Translation2d".to_string(),
+            "structschema".to_string(),
+            TableValue::Raw(b"double x;double y".to_vec()),
+            0,
+        ));
+        ingest
+            .announce(TopicInfo {
+                name: "/Drive/Position".to_string(),
+                value_type: "struct:Translation2d".to_string(),
+                id: Some(1),
+                properties: serde_json::json!({}),
+                persistent: false,
+                retained: false,
+                cached: true,
+                announced: true,
+                first_seen: 0,
+                last_seen: 0,
+                rows: 0,
+                source: None,
+            })
+            .await;
+        let bytes: Vec<u8> = [1.0f64, -2.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        ingest.push(TableEntree::new(
+            "/Drive/Position".to_string(),
+            TableValue::Raw(bytes.clone()),
+            1,
+        ));
+        drop(ingest);
+        task.await.unwrap();
+
+        let entree = database
+            .lock()
+            .unwrap()
+            .get_value("/Drive/Position")
+            .unwrap();
+        assert_eq!(entree.value_type, "struct:Translation2d");
+        assert_eq!(entree.value, TableValue::Raw(bytes));
+        assert_eq!(
+            entree.decoded,
+            Some(serde_json::json!({"x": 1.0, "y": -2.0}))
+        );
+    }
+}
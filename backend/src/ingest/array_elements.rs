@@ -0,0 +1,109 @@
+use crate::{
+    database::structs::{table_entree::TableEntree, table_value::TableValue},
+    network_table_bridge::topic_filter::TopicFilter,
+};
+
+///
+/// # Function
+/// Records the elements of array topics as their own topics, so one element can be charted over time. Element `i` of
+/// `/Drive/ModuleAngles` becomes `/Drive/ModuleAngles[i]` with the type of the element (e.g. `double`). The array itself is
+/// still recorded as it is. Only the topics that match the filter are split, nothing extra is stored for the others.
+///
+/// # Types
+/// `boolean[]`, `int[]`, `double[]`, `float[]` and `string[]`. Raw values and struct arrays are not split.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayElements {
+    filter: TopicFilter,
+}
+
+impl ArrayElements {
+    ///
+    /// # Function
+    /// Makes the splitter from a comma separated list of patterns (same format as `TopicFilter`).
+    ///
+    /// # Returns
+    /// `None` if the list is empty, a filter without patterns would split every array topic
+    ///
+    pub fn from_list(list: &str) -> Option<Self> {
+        let filter = TopicFilter::from_lists(list, "");
+        filter.has_allowlist().then_some(Self { filter })
+    }
+
+    ///
+    /// # Function
+    /// Makes one entry per element of an array entry. The elements keep the timestamp, wall clock time and source of the array.
+    ///
+    /// # Returns
+    /// Nothing if the topic does not match or the value is not an array
+    ///
+    pub fn split(&self, entree: &TableEntree) -> Vec<TableEntree> {
+        if !self.filter.matches(&entree.topic) {
+            return Vec::new();
+        }
+
+        let values: Vec<TableValue> = match &entree.value {
+            TableValue::BooleanArray(values) => {
+                values.iter().copied().map(TableValue::Boolean).collect()
+            }
+            TableValue::IntArray(values) => values.iter().copied().map(TableValue::Int).collect(),
+            TableValue::DoubleArray(values) => {
+                values.iter().copied().map(TableValue::Double).collect()
+            }
+            TableValue::FloatArray(values) => {
+                values.iter().copied().map(TableValue::Float).collect()
+            }
+            TableValue::StringArray(values) => {
+                values.iter().cloned().map(TableValue::String).collect()
+            }
+            _ => return Vec::new(),
+        };
+
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let mut element = TableEntree::new(
+                    format!("{}[{}]", entree.topic, index),
+                    value,
+                    entree.timestamp,
+                );
+                element.wall_time = entree.wall_time;
+                element.source = entree.source.clone();
+                element
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let elements = ArrayElements::from_list("/Drive/*").unwrap();
+        let mut entree = TableEntree::new(
+            "/Drive/ModuleAngles".to_string(),
+            TableValue::DoubleArray(vec![0.5, 1.5, 2.5]),
+            7,
+        );
+        entree.source = Some("robot".to_string());
+
+        let split = elements.split(&entree);
+        assert_eq!(split.len(), 3);
+        assert_eq!(split[2].topic, "/Drive/ModuleAngles[2]");
+        assert_eq!(split[2].value_type, "double");
+        assert_eq!(split[2].value, TableValue::Double(2.5));
+        assert_eq!(split[2].timestamp, 7);
+        assert_eq!(split[2].source.as_deref(), Some("robot"));
+
+        // not a matching topic, not an array
+        entree.topic = "/Arm/Angles".to_string();
+        assert!(elements.split(&entree).is_empty());
+        let scalar = TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(1.0), 7);
+        assert!(elements.split(&scalar).is_empty());
+
+        assert_eq!(ArrayElements::from_list(" , "), None);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use crate::{
+    database::structs::{table_entree::TableEntree, table_value::TableValue},
+    network_table_bridge::topic_filter::TopicFilter,
+};
+
+///
+/// # Function
+/// Settings for change-only recording.
+///
+/// # Fields
+/// - `deadbands`: Patterns (same format as `TopicFilter`) with the amount a number has to change before it is recorded again.
+///   The first pattern that matches is used, topics that match none only drop values that are exactly the same
+/// - `keyframe_interval`: An unchanged value is still recorded if nothing was recorded for the topic for this long (microseconds).
+///   This keeps the value of a topic inside every time window that is queried or cleaned. `0` turns keyframes off
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeduplicationConfig {
+    pub deadbands: Vec<(TopicFilter, f64)>,
+    pub keyframe_interval: u64,
+}
+
+impl Default for DeduplicationConfig {
+    fn default() -> Self {
+        Self {
+            deadbands: Vec::new(),
+            keyframe_interval: 1_000_000,
+        }
+    }
+}
+
+impl DeduplicationConfig {
+    ///
+    /// # Function
+    /// Reads the deadbands from a comma separated list of `pattern=deadband` (the format used in the .env file), e.g.
+    /// `/SmartDashboard/Drive/*=0.01,/Arm/**=0.5`. Entries without a valid number are ignored.
+    ///
+    pub fn parse_deadbands(list: &str) -> Vec<(TopicFilter, f64)> {
+        list.split(',')
+            .filter_map(|entry| {
+                let (pattern, deadband) = entry.rsplit_once('=')?;
+                let deadband = deadband.trim().parse::<f64>().ok()?;
+                let pattern = pattern.trim();
+                (!pattern.is_empty())
+                    .then(|| (TopicFilter::from_lists(pattern, ""), deadband.abs()))
+            })
+            .collect()
+    }
+}
+
+///
+/// # Function
+/// Drops values that did not change from the last recorded value of their topic (within the deadband of the topic).
+/// Values are compared to the last *recorded* value, so a value that slowly drifts is still recorded once it moved
+/// more than the deadband. Every source is compared on its own: the same topic from two robots is two topics, each with
+/// its own clock.
+///
+#[derive(Debug)]
+pub struct Deduplicator {
+    config: DeduplicationConfig,
+    /// (source, topic) -> the last recorded value and timestamp
+    last: HashMap<(Option<String>, String), (TableValue, u64)>,
+}
+
+impl Deduplicator {
+    pub fn new(config: DeduplicationConfig) -> Self {
+        Self {
+            config,
+            last: HashMap::new(),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Checks if an entry should be recorded and remembers it if it should.
+    ///
+    pub fn keep(&mut self, entree: &TableEntree) -> bool {
+        let key = (entree.source.clone(), entree.topic.clone());
+        if let Some((value, timestamp)) = self.last.get(&key) {
+            let keyframe_due = self.config.keyframe_interval > 0
+                && entree.timestamp.saturating_sub(*timestamp) >= self.config.keyframe_interval;
+            if !keyframe_due && within_deadband(value, &entree.value, self.deadband(&entree.topic))
+            {
+                return false;
+            }
+        }
+
+        self.last
+            .insert(key, (entree.value.clone(), entree.timestamp));
+        true
+    }
+
+    ///
+    /// # Function
+    /// Forgets every topic, so the next value of each topic is recorded. Called when a new session starts.
+    ///
+    pub fn reset(&mut self) {
+        self.last.clear();
+    }
+
+    fn deadband(&self, topic: &str) -> f64 {
+        self.config
+            .deadbands
+            .iter()
+            .find(|(filter, _)| filter.matches(topic))
+            .map(|(_, deadband)| *deadband)
+            .unwrap_or(0.0)
+    }
+}
+
+fn within_deadband(last: &TableValue, new: &TableValue, deadband: f64) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() <= deadband;
+
+    match (last, new) {
+        (TableValue::Int(a), TableValue::Int(b)) => a.abs_diff(*b) as f64 <= deadband,
+        (TableValue::Double(a), TableValue::Double(b)) => close(*a, *b),
+        (TableValue::Float(a), TableValue::Float(b)) => close(*a as f64, *b as f64),
+        (TableValue::IntArray(a), TableValue::IntArray(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| a.abs_diff(*b) as f64 <= deadband)
+        }
+        (TableValue::DoubleArray(a), TableValue::DoubleArray(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| close(*a, *b))
+        }
+        (TableValue::FloatArray(a), TableValue::FloatArray(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| close(*a as f64, *b as f64))
+        }
+        _ => last == new,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entree(topic: &str, value: TableValue, timestamp: u64) -> TableEntree {
+        TableEntree::new(topic.to_string(), value, timestamp)
+    }
+
+    #[test]
+    fn test_drops_unchanged_values() {
+        let mut deduplicator = Deduplicator::new(DeduplicationConfig {
+            keyframe_interval: 0,
+            ..Default::default()
+        });
+
+        assert!(deduplicator.keep(&entree("/Enabled", TableValue::Boolean(true), 0)));
+        assert!(!deduplicator.keep(&entree("/Enabled", TableValue::Boolean(true), 1)));
+        assert!(deduplicator.keep(&entree("/Other", TableValue::Boolean(true), 1)));
+        assert!(deduplicator.keep(&entree("/Enabled", TableValue::Boolean(false), 2)));
+        // a different type is a change
+        assert!(deduplicator.keep(&entree("/Enabled", TableValue::Int(0), 3)));
+
+        deduplicator.reset();
+        assert!(deduplicator.keep(&entree("/Enabled", TableValue::Int(0), 0)));
+    }
+
+    #[test]
+    fn test_deadband_and_keyframes() {
+        let mut deduplicator = Deduplicator::new(DeduplicationConfig {
+            deadbands: DeduplicationConfig::parse_deadbands("/Drive/*=0.1, invalid=abc"),
+            keyframe_interval: 1_000_000,
+        });
+
+        assert!(deduplicator.keep(&entree("/Drive/Speed", TableValue::Double(1.0), 0)));
+        assert!(!deduplicator.keep(&entree("/Drive/Speed", TableValue::Double(1.05), 10)));
+        // compared to the last recorded value (1.0), not the last received one (1.05)
+        assert!(!deduplicator.keep(&entree("/Drive/Speed", TableValue::Double(1.08), 20)));
+        assert!(deduplicator.keep(&entree("/Drive/Speed", TableValue::Double(1.15), 30)));
+        assert!(deduplicator.keep(&entree("/Drive/Speed", TableValue::Double(1.15), 1_000_030)));
+
+        // no deadband outside of the pattern
+        assert!(deduplicator.keep(&entree("/Arm/Angle", TableValue::Double(1.0), 0)));
+        assert!(deduplicator.keep(&entree("/Arm/Angle", TableValue::Double(1.05), 10)));
+    }
+
+    #[test]
+    fn test_sources_are_apart() {
+        let mut deduplicator = Deduplicator::new(DeduplicationConfig::default());
+        let from = |source: &str, value: f64, timestamp| {
+            let mut entree = entree("/Drive/Speed", TableValue::Double(value), timestamp);
+            entree.source = Some(source.to_string());
+            entree
+        };
+
+        assert!(deduplicator.keep(&from("robot", 1.0, 5_000_000)));
+        // the same value from another robot is still its first value
+        assert!(deduplicator.keep(&from("sim", 1.0, 10)));
+        assert!(!deduplicator.keep(&from("sim", 1.0, 20)));
+        // the keyframe of a source only counts its own clock
+        assert!(!deduplicator.keep(&from("robot", 1.0, 5_000_010)));
+        assert!(deduplicator.keep(&from("sim", 1.0, 1_000_010)));
+    }
+
+    #[test]
+    fn test_array_deadband() {
+        let mut deduplicator = Deduplicator::new(DeduplicationConfig {
+            deadbands: DeduplicationConfig::parse_deadbands("/Pose=0.5"),
+            keyframe_interval: 0,
+        });
+
+        let pose = |values: Vec<f64>, timestamp| {
+            entree("/Pose", TableValue::DoubleArray(values), timestamp)
+        };
+        assert!(deduplicator.keep(&pose(vec![1.0, 2.0], 0)));
+        assert!(!deduplicator.keep(&pose(vec![1.2, 2.3], 1)));
+        assert!(deduplicator.keep(&pose(vec![1.2, 2.6], 2)));
+        assert!(deduplicator.keep(&pose(vec![1.2, 2.6, 0.0], 3)));
+    }
+}
@@ -0,0 +1,637 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::database::structs::{table_entree::TableEntree, table_value::TableValue};
+
+/// The prefix of the topics that WPILib publishes the protobuf schemas on (e.g. `/.schema/proto:geometry2d.proto`)
+pub const SCHEMA_PREFIX: &str = "/.schema/proto:";
+/// The prefix of the type string of protobuf topics, followed by the full name of the message (e.g. `proto:wpi.proto.ProtobufPose2d`)
+const TYPE_PREFIX: &str = "proto:";
+/// Messages can contain messages, this stops a message that contains itself
+const MAX_DEPTH: usize = 32;
+
+/// The field types of `FieldDescriptorProto.Type`
+mod field_type {
+    pub const DOUBLE: u64 = 1;
+    pub const FLOAT: u64 = 2;
+    pub const INT64: u64 = 3;
+    pub const UINT64: u64 = 4;
+    pub const INT32: u64 = 5;
+    pub const FIXED64: u64 = 6;
+    pub const FIXED32: u64 = 7;
+    pub const BOOL: u64 = 8;
+    pub const STRING: u64 = 9;
+    pub const MESSAGE: u64 = 11;
+    pub const BYTES: u64 = 12;
+    pub const UINT32: u64 = 13;
+    pub const ENUM: u64 = 14;
+    pub const SFIXED32: u64 = 15;
+    pub const SFIXED64: u64 = 16;
+    pub const SINT32: u64 = 17;
+    pub const SINT64: u64 = 18;
+}
+
+/// `FieldDescriptorProto.Label.LABEL_REPEATED`
+const LABEL_REPEATED: u64 = 3;
+
+///
+/// # Function
+/// A value of the protobuf wire format.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl Wire<'_> {
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Wire::Varint(value) | Wire::Fixed64(value) => Some(*value),
+            Wire::Fixed32(value) => Some(*value as u64),
+            Wire::Bytes(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<String> {
+        match self {
+            Wire::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+fn read_fixed<const N: usize>(bytes: &[u8], position: &mut usize) -> Option<[u8; N]> {
+    let data = bytes.get(*position..*position + N)?.try_into().ok()?;
+    *position += N;
+    Some(data)
+}
+
+///
+/// # Function
+/// Splits an encoded message into its fields (field number and value), in the order they were written.
+///
+/// # Returns
+/// `None` if the data is not a valid message (groups are not supported, they are deprecated)
+///
+fn read_fields(bytes: &[u8]) -> Option<Vec<(u64, Wire<'_>)>> {
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let key = read_varint(bytes, &mut position)?;
+        let value = match key & 0x7 {
+            0 => Wire::Varint(read_varint(bytes, &mut position)?),
+            1 => Wire::Fixed64(u64::from_le_bytes(read_fixed(bytes, &mut position)?)),
+            2 => {
+                let length = read_varint(bytes, &mut position)? as usize;
+                let data = bytes.get(position..position.checked_add(length)?)?;
+                position += length;
+                Wire::Bytes(data)
+            }
+            5 => Wire::Fixed32(u32::from_le_bytes(read_fixed(bytes, &mut position)?)),
+            _ => return None,
+        };
+        fields.push((key >> 3, value));
+    }
+
+    Some(fields)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FieldDescriptor {
+    name: String,
+    number: u64,
+    r#type: u64,
+    repeated: bool,
+    /// The full name of the message or enum type, without the leading `.`
+    type_name: String,
+}
+
+impl FieldDescriptor {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut field = FieldDescriptor {
+            name: String::new(),
+            number: 0,
+            r#type: 0,
+            repeated: false,
+            type_name: String::new(),
+        };
+        for (number, value) in read_fields(bytes)? {
+            match number {
+                1 => field.name = value.as_str()?,
+                3 => field.number = value.as_u64()?,
+                4 => field.repeated = value.as_u64()? == LABEL_REPEATED,
+                5 => field.r#type = value.as_u64()?,
+                6 => field.type_name = value.as_str()?.trim_start_matches('.').to_string(),
+                _ => {}
+            }
+        }
+
+        Some(field)
+    }
+
+    /// The value a proto3 field has when it is not in the message, `None` for messages (they are left out)
+    fn default_value(&self) -> Option<JsonValue> {
+        if self.repeated {
+            return Some(JsonValue::Array(Vec::new()));
+        }
+
+        Some(match self.r#type {
+            field_type::MESSAGE => return None,
+            field_type::BOOL => JsonValue::Bool(false),
+            field_type::STRING => JsonValue::String(String::new()),
+            field_type::BYTES => JsonValue::Array(Vec::new()),
+            field_type::DOUBLE | field_type::FLOAT => JsonValue::from(0.0),
+            _ => JsonValue::from(0),
+        })
+    }
+}
+
+///
+/// # Function
+/// Decodes the payloads of protobuf topics (`proto:wpi.proto.ProtobufPose2d`, ...) into JSON objects with the field names
+/// of the message. The messages are learned from the `FileDescriptorProto`s that the robot publishes on the
+/// `/.schema/proto:*` topics, and the type of a topic is learned from its announcement (or from the type of the entry).
+/// Payloads of messages that are not known (yet) keep only their raw bytes.
+///
+/// # JSON
+/// Fields that are not in the payload get their proto3 default, except for messages which are left out. Enums are written
+/// as the name of the value if it is known, `bytes` fields as an array of bytes and 64-bit integers as JSON numbers.
+///
+#[derive(Debug, Default)]
+pub struct ProtoDecoder {
+    /// Full message name -> fields
+    messages: HashMap<String, Vec<FieldDescriptor>>,
+    /// Full enum name -> value number -> value name
+    enums: HashMap<String, HashMap<i64, String>>,
+    /// (source, topic) -> announced type string. Two connections can have the same topic with different types
+    types: HashMap<(Option<String>, String), String>,
+}
+
+impl ProtoDecoder {
+    ///
+    /// # Function
+    /// Remembers the type of a topic from its announcement.
+    ///
+    /// # Parameters
+    /// - `source`: The connection that announced the topic (the `source` of its entries)
+    /// - `topic`: The topic name
+    /// - `r#type`: The announced type string
+    ///
+    pub fn set_type(&mut self, source: Option<&str>, topic: &str, r#type: &str) {
+        let key = (source.map(str::to_string), topic.to_string());
+        if r#type.starts_with(TYPE_PREFIX) {
+            self.types.insert(key, r#type.to_string());
+        } else {
+            self.types.remove(&key);
+        }
+    }
+
+    ///
+    /// # Function
+    /// Adds every message and enum of an encoded `FileDescriptorProto` to the pool.
+    ///
+    /// # Returns
+    /// `None` if the data is not a `FileDescriptorProto`
+    ///
+    pub fn add_file(&mut self, bytes: &[u8]) -> Option<()> {
+        let fields = read_fields(bytes)?;
+        let package = fields
+            .iter()
+            .find(|(number, _)| *number == 2)
+            .and_then(|(_, value)| value.as_str())
+            .unwrap_or_default();
+
+        for (number, value) in fields {
+            let Wire::Bytes(data) = value else {
+                continue;
+            };
+            match number {
+                4 => self.add_message(&package, data)?,
+                5 => self.add_enum(&package, data)?,
+                _ => {}
+            }
+        }
+
+        Some(())
+    }
+
+    fn add_message(&mut self, scope: &str, bytes: &[u8]) -> Option<()> {
+        let fields = read_fields(bytes)?;
+        let name = fields
+            .iter()
+            .find(|(number, _)| *number == 1)
+            .and_then(|(_, value)| value.as_str())?;
+        let name = full_name(scope, &name);
+
+        let mut descriptors = Vec::new();
+        for (number, value) in fields {
+            let Wire::Bytes(data) = value else {
+                continue;
+            };
+            match number {
+                2 => descriptors.push(FieldDescriptor::parse(data)?),
+                3 => self.add_message(&name, data)?,
+                4 => self.add_enum(&name, data)?,
+                _ => {}
+            }
+        }
+
+        self.messages.insert(name, descriptors);
+        Some(())
+    }
+
+    fn add_enum(&mut self, scope: &str, bytes: &[u8]) -> Option<()> {
+        let mut name = String::new();
+        let mut values = HashMap::new();
+        for (number, value) in read_fields(bytes)? {
+            match (number, value) {
+                (1, value) => name = value.as_str()?,
+                (2, Wire::Bytes(data)) => {
+                    let mut value_name = String::new();
+                    let mut value_number = 0;
+                    for (number, value) in read_fields(data)? {
+                        match number {
+                            1 => value_name = value.as_str()?,
+                            2 => value_number = value.as_u64()? as i32 as i64,
+                            _ => {}
+                        }
+                    }
+                    values.insert(value_number, value_name);
+                }
+                _ => {}
+            }
+        }
+
+        self.enums.insert(full_name(scope, &name), values);
+        Some(())
+    }
+
+    ///
+    /// # Function
+    /// Learns the schemas if the entry is a schema topic, or fills in `decoded` (and the protobuf type) if it is a protobuf
+    /// topic whose message is known. Anything else is left as it is, so the raw bytes are always kept.
+    ///
+    pub fn decode(&mut self, entree: &mut TableEntree) {
+        if entree.topic.starts_with(SCHEMA_PREFIX) {
+            if let TableValue::Raw(bytes) = &entree.value {
+                if self.add_file(bytes).is_none() {
+                    println!("Invalid protobuf schema on {}", entree.topic);
+                }
+            }
+            return;
+        }
+
+        let r#type = if entree.value_type.starts_with(TYPE_PREFIX) {
+            entree.value_type.clone()
+        } else if let Some(r#type) = self
+            .types
+            .get(&(entree.source.clone(), entree.topic.clone()))
+        {
+            r#type.clone()
+        } else {
+            return;
+        };
+        let TableValue::Raw(bytes) = &entree.value else {
+            return;
+        };
+
+        if let Some(decoded) = self.decode_message(&r#type[TYPE_PREFIX.len()..], bytes) {
+            entree.value_type = r#type;
+            entree.decoded = Some(decoded);
+        }
+    }
+
+    ///
+    /// # Function
+    /// Decodes an encoded message.
+    ///
+    /// # Parameters
+    /// - `name`: The full name of the message (e.g. `wpi.proto.ProtobufPose2d`)
+    /// - `bytes`: The encoded message
+    ///
+    /// # Returns
+    /// `None` if the message (or a message inside it) is not known or the data does not fit it
+    ///
+    pub fn decode_message(&self, name: &str, bytes: &[u8]) -> Option<JsonValue> {
+        self.read_message(name, bytes, 0)
+    }
+
+    fn read_message(&self, name: &str, bytes: &[u8], depth: usize) -> Option<JsonValue> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let descriptors = self.messages.get(name)?;
+        let mut object = Map::new();
+        for descriptor in descriptors {
+            if let Some(default) = descriptor.default_value() {
+                object.insert(descriptor.name.clone(), default);
+            }
+        }
+
+        for (number, value) in read_fields(bytes)? {
+            // unknown fields are skipped, like every protobuf parser does
+            let Some(descriptor) = descriptors.iter().find(|field| field.number == number) else {
+                continue;
+            };
+
+            let values = match value {
+                Wire::Bytes(data) if descriptor.repeated && is_packable(descriptor.r#type) => {
+                    self.read_packed(descriptor, data)?
+                }
+                value => vec![self.read_value(descriptor, value, depth)?],
+            };
+
+            if descriptor.repeated {
+                if let Some(JsonValue::Array(array)) = object.get_mut(&descriptor.name) {
+                    array.extend(values);
+                }
+            } else if let Some(value) = values.into_iter().next() {
+                // the last value wins for fields that are not repeated
+                object.insert(descriptor.name.clone(), value);
+            }
+        }
+
+        Some(JsonValue::Object(object))
+    }
+
+    fn read_packed(&self, descriptor: &FieldDescriptor, bytes: &[u8]) -> Option<Vec<JsonValue>> {
+        let mut values = Vec::new();
+        let mut position = 0;
+        while position < bytes.len() {
+            let value = match descriptor.r#type {
+                field_type::DOUBLE | field_type::FIXED64 | field_type::SFIXED64 => {
+                    Wire::Fixed64(u64::from_le_bytes(read_fixed(bytes, &mut position)?))
+                }
+                field_type::FLOAT | field_type::FIXED32 | field_type::SFIXED32 => {
+                    Wire::Fixed32(u32::from_le_bytes(read_fixed(bytes, &mut position)?))
+                }
+                _ => Wire::Varint(read_varint(bytes, &mut position)?),
+            };
+            values.push(self.read_value(descriptor, value, 0)?);
+        }
+
+        Some(values)
+    }
+
+    fn read_value(
+        &self,
+        descriptor: &FieldDescriptor,
+        value: Wire<'_>,
+        depth: usize,
+    ) -> Option<JsonValue> {
+        if let Wire::Bytes(data) = value {
+            return match descriptor.r#type {
+                field_type::STRING => value.as_str().map(JsonValue::String),
+                field_type::BYTES => Some(JsonValue::from(data.to_vec())),
+                field_type::MESSAGE => self.read_message(&descriptor.type_name, data, depth + 1),
+                _ => None,
+            };
+        }
+
+        let raw = value.as_u64()?;
+        Some(match (descriptor.r#type, value) {
+            (field_type::DOUBLE, Wire::Fixed64(_)) => JsonValue::from(f64::from_bits(raw)),
+            (field_type::FLOAT, Wire::Fixed32(_)) => {
+                JsonValue::from(f32::from_bits(raw as u32) as f64)
+            }
+            (field_type::FIXED64, Wire::Fixed64(_)) => JsonValue::from(raw),
+            (field_type::SFIXED64, Wire::Fixed64(_)) => JsonValue::from(raw as i64),
+            (field_type::FIXED32, Wire::Fixed32(_)) => JsonValue::from(raw as u32),
+            (field_type::SFIXED32, Wire::Fixed32(_)) => JsonValue::from(raw as u32 as i32),
+            (field_type::BOOL, Wire::Varint(_)) => JsonValue::Bool(raw != 0),
+            (field_type::INT64, Wire::Varint(_)) => JsonValue::from(raw as i64),
+            (field_type::UINT64, Wire::Varint(_)) => JsonValue::from(raw),
+            (field_type::INT32, Wire::Varint(_)) => JsonValue::from(raw as i32),
+            (field_type::UINT32, Wire::Varint(_)) => JsonValue::from(raw as u32),
+            (field_type::SINT32 | field_type::SINT64, Wire::Varint(_)) => {
+                JsonValue::from((raw >> 1) as i64 ^ -((raw & 1) as i64))
+            }
+            (field_type::ENUM, Wire::Varint(_)) => {
+                let number = raw as i32 as i64;
+                match self
+                    .enums
+                    .get(&descriptor.type_name)
+                    .and_then(|values| values.get(&number))
+                {
+                    Some(name) => JsonValue::String(name.clone()),
+                    None => JsonValue::from(number),
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Numeric repeated fields can be packed into one length-delimited value
+fn is_packable(r#type: u64) -> bool {
+    !matches!(
+        r#type,
+        field_type::STRING | field_type::BYTES | field_type::MESSAGE
+    )
+}
+
+fn full_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn varint_field(number: u64, value: u64) -> Vec<u8> {
+        let mut bytes = varint(number << 3);
+        bytes.extend(varint(value));
+        bytes
+    }
+
+    fn bytes_field(number: u64, data: &[u8]) -> Vec<u8> {
+        let mut bytes = varint((number << 3) | 2);
+        bytes.extend(varint(data.len() as u64));
+        bytes.extend(data);
+        bytes
+    }
+
+    fn double_field(number: u64, value: f64) -> Vec<u8> {
+        let mut bytes = varint((number << 3) | 1);
+        bytes.extend(value.to_le_bytes());
+        bytes
+    }
+
+    /// An encoded `FieldDescriptorProto`
+    fn field(name: &str, number: u64, r#type: u64, label: u64, type_name: &str) -> Vec<u8> {
+        [
+            bytes_field(1, name.as_bytes()),
+            varint_field(3, number),
+            varint_field(4, label),
+            varint_field(5, r#type),
+            bytes_field(6, type_name.as_bytes()),
+        ]
+        .concat()
+    }
+
+    fn message(name: &str, fields: &[Vec<u8>], nested: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = bytes_field(1, name.as_bytes());
+        for field in fields {
+            bytes.extend(bytes_field(2, field));
+        }
+        for enumeration in nested {
+            bytes.extend(bytes_field(4, enumeration));
+        }
+        bytes
+    }
+
+    /// `package wpi.proto;` with `ProtobufTranslation2d {double x = 1; double y = 2;}` and
+    /// `Status {Translation2d position = 1; repeated int32 ids = 2; Mode mode = 3; string name = 4; sint32 offset = 5;}`
+    fn file() -> Vec<u8> {
+        let translation = message(
+            "ProtobufTranslation2d",
+            &[
+                field("x", 1, field_type::DOUBLE, 1, ""),
+                field("y", 2, field_type::DOUBLE, 1, ""),
+            ],
+            &[],
+        );
+        let mode = [
+            bytes_field(1, b"Mode"),
+            bytes_field(2, &[bytes_field(1, b"IDLE"), varint_field(2, 0)].concat()),
+            bytes_field(2, &[bytes_field(1, b"AUTO"), varint_field(2, 1)].concat()),
+        ]
+        .concat();
+        let status = message(
+            "Status",
+            &[
+                field(
+                    "position",
+                    1,
+                    field_type::MESSAGE,
+                    1,
+                    ".wpi.proto.ProtobufTranslation2d",
+                ),
+                field("ids", 2, field_type::INT32, LABEL_REPEATED, ""),
+                field("mode", 3, field_type::ENUM, 1, ".wpi.proto.Status.Mode"),
+                field("name", 4, field_type::STRING, 1, ""),
+                field("offset", 5, field_type::SINT32, 1, ""),
+            ],
+            &[mode],
+        );
+
+        [
+            bytes_field(1, b"status.proto"),
+            bytes_field(2, b"wpi.proto"),
+            bytes_field(4, &translation),
+            bytes_field(4, &status),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_decode_message() {
+        let mut decoder = ProtoDecoder::default();
+        decoder.add_file(&file()).unwrap();
+
+        let translation = [double_field(1, 1.5), double_field(2, -2.0)].concat();
+        assert_eq!(
+            decoder.decode_message("wpi.proto.ProtobufTranslation2d", &translation),
+            Some(serde_json::json!({"x": 1.5, "y": -2.0}))
+        );
+        // proto3 leaves out fields that have their default value
+        assert_eq!(
+            decoder.decode_message("wpi.proto.ProtobufTranslation2d", &double_field(2, 3.0)),
+            Some(serde_json::json!({"x": 0.0, "y": 3.0}))
+        );
+
+        let packed_ids = [varint(1), varint(2), varint(u64::MAX)].concat(); // -1 as an int32 takes 10 bytes
+        let status = [
+            bytes_field(1, &translation),
+            bytes_field(2, &packed_ids),
+            varint_field(2, 7), // not packed
+            varint_field(3, 1),
+            bytes_field(4, b"arm"),
+            varint_field(5, 3),  // zigzag for -2
+            varint_field(99, 1), // unknown
+        ]
+        .concat();
+        assert_eq!(
+            decoder.decode_message("wpi.proto.Status", &status),
+            Some(serde_json::json!({
+                "position": {"x": 1.5, "y": -2.0},
+                "ids": [1, 2, -1, 7],
+                "mode": "AUTO",
+                "name": "arm",
+                "offset": -2
+            }))
+        );
+    }
+
+    #[test]
+    fn test_entree_falls_back_to_raw() {
+        let mut decoder = ProtoDecoder::default();
+        let payload = [double_field(1, 1.0), double_field(2, 2.0)].concat();
+        let mut entree = TableEntree::with_type(
+            "/Drive/Position".to_string(),
+            "proto:wpi.proto.ProtobufTranslation2d".to_string(),
+            TableValue::Raw(payload.clone()),
+            1,
+        );
+
+        // the schema is missing
+        let mut missing = entree.clone();
+        decoder.decode(&mut missing);
+        assert_eq!(missing.decoded, None);
+        assert_eq!(missing.value, TableValue::Raw(payload.clone()));
+
+        decoder.decode(&mut TableEntree::with_type(
+            "/.schema/proto:status.proto".to_string(),
+            "proto:FileDescriptorProto".to_string(),
+            TableValue::Raw(file()),
+            0,
+        ));
+        decoder.decode(&mut entree);
+        assert_eq!(
+            entree.decoded,
+            Some(serde_json::json!({"x": 1.0, "y": 2.0}))
+        );
+
+        // invalid data
+        assert_eq!(
+            decoder.decode_message("wpi.proto.ProtobufTranslation2d", &[0x0a, 0x05, 0x01]),
+            None
+        );
+    }
+}
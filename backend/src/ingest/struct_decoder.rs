@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value as JsonValue};
+
+use crate::database::structs::{table_entree::TableEntree, table_value::TableValue};
+
+/// The prefix of the topics that WPILib publishes the struct schemas on (e.g. `/.schema/struct:Pose2d`)
+pub const SCHEMA_PREFIX: &str = "/.schema/struct:";
+/// The prefix of the type string of struct topics (e.g. `struct:Pose2d` or `struct:SwerveModuleState[]`)
+const TYPE_PREFIX: &str = "struct:";
+/// Structs can contain structs, this stops a schema that (by mistake) contains itself
+const MAX_DEPTH: usize = 16;
+
+///
+/// # Function
+/// The type of a field of a struct.
+///
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    Bool,
+    /// A character, arrays of them are read as a string
+    Char,
+    Integer {
+        bytes: usize,
+        signed: bool,
+    },
+    Float,
+    Double,
+    /// Another struct, by name
+    Struct(String),
+}
+
+impl FieldType {
+    fn parse(name: &str) -> Self {
+        let integer = |bytes, signed| FieldType::Integer { bytes, signed };
+        match name {
+            "bool" => FieldType::Bool,
+            "char" => FieldType::Char,
+            "int8" => integer(1, true),
+            "int16" => integer(2, true),
+            "int32" => integer(4, true),
+            "int64" => integer(8, true),
+            "uint8" => integer(1, false),
+            "uint16" => integer(2, false),
+            "uint32" => integer(4, false),
+            "uint64" => integer(8, false),
+            "float" | "float32" => FieldType::Float,
+            "double" | "float64" => FieldType::Double,
+            other => FieldType::Struct(other.to_string()),
+        }
+    }
+
+    /// The size of the type in bytes, `None` for structs (their size depends on their schema)
+    fn size(&self) -> Option<usize> {
+        match self {
+            FieldType::Bool | FieldType::Char => Some(1),
+            FieldType::Integer { bytes, .. } => Some(*bytes),
+            FieldType::Float => Some(4),
+            FieldType::Double => Some(8),
+            FieldType::Struct(_) => None,
+        }
+    }
+}
+
+///
+/// # Function
+/// One declaration of a struct schema, e.g. `double x`, `uint8 flags[4]` or `int32 mode:3`.
+///
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    name: String,
+    r#type: FieldType,
+    /// The length if the field is an array
+    array: Option<usize>,
+    /// The width in bits if the field is a bit-field
+    bits: Option<u32>,
+}
+
+///
+/// # Function
+/// Reads a WPILib struct schema, e.g. `Translation2d translation;Rotation2d rotation`. The `enum {...}` part of a declaration
+/// is skipped, enum fields are decoded as their number.
+///
+/// # Returns
+/// An error with the declaration that could not be read
+///
+fn parse_schema(schema: &str) -> Result<Vec<Field>, String> {
+    let mut fields = Vec::new();
+    for declaration in schema.split(';') {
+        let mut declaration = declaration.trim();
+        if declaration.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = declaration.strip_prefix("enum") {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format!("unclosed enum in `{}`", declaration))?;
+            declaration = rest[end + 1..].trim();
+        }
+
+        let (declaration, bits) = match declaration.split_once(':') {
+            Some((declaration, bits)) => {
+                let bits = bits
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid bit-field width in `{}`", declaration))?;
+                (declaration.trim(), Some(bits))
+            }
+            None => (declaration, None),
+        };
+
+        let (declaration, array) = match declaration.split_once('[') {
+            Some((declaration, length)) => {
+                let length = length
+                    .trim_end()
+                    .strip_suffix(']')
+                    .and_then(|length| length.trim().parse::<usize>().ok())
+                    .ok_or_else(|| format!("invalid array length in `{}`", declaration))?;
+                (declaration.trim(), Some(length))
+            }
+            None => (declaration, None),
+        };
+
+        let mut parts = declaration.split_whitespace();
+        let (Some(r#type), Some(name), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("expected `type name` in `{}`", declaration));
+        };
+        let r#type = FieldType::parse(r#type);
+        if bits.is_some()
+            && (array.is_some() || !matches!(r#type, FieldType::Bool | FieldType::Integer { .. }))
+        {
+            return Err(format!("`{}` can not be a bit-field", name));
+        }
+
+        fields.push(Field {
+            name: name.to_string(),
+            r#type,
+            array,
+            bits,
+        });
+    }
+
+    Ok(fields)
+}
+
+///
+/// # Function
+/// Decodes the raw bytes of WPILib struct topics (`struct:Pose2d`, `struct:SwerveModuleState[]`, ...) into JSON objects with
+/// the field names of the struct. The schemas are learned from the `/.schema/struct:*` topics that the robot publishes, and
+/// the type of a topic is learned from its announcement (or from the type of the entry if the source already knows it).
+///
+/// # Encoding
+/// Fields are little endian and packed without padding. Consecutive bit-fields share one integer of the size of their type
+/// as long as they fit in it, `bool` bit-fields are stored in a `uint8`.
+///
+#[derive(Debug, Default)]
+pub struct StructDecoder {
+    /// Struct name -> fields
+    schemas: HashMap<String, Vec<Field>>,
+    /// (source, topic) -> announced type string. Two connections can have the same topic with different types
+    types: HashMap<(Option<String>, String), String>,
+}
+
+impl StructDecoder {
+    ///
+    /// # Function
+    /// Remembers the type of a topic from its announcement.
+    ///
+    /// # Parameters
+    /// - `source`: The connection that announced the topic (the `source` of its entries)
+    /// - `topic`: The topic name
+    /// - `r#type`: The announced type string
+    ///
+    pub fn set_type(&mut self, source: Option<&str>, topic: &str, r#type: &str) {
+        let key = (source.map(str::to_string), topic.to_string());
+        if r#type.starts_with(TYPE_PREFIX) {
+            self.types.insert(key, r#type.to_string());
+        } else {
+            self.types.remove(&key);
+        }
+    }
+
+    ///
+    /// # Function
+    /// Adds a schema, e.g. the name `Translation2d` with the schema `double x;double y`.
+    ///
+    pub fn add_schema(&mut self, name: &str, schema: &str) -> Result<(), String> {
+        let fields = parse_schema(schema)?;
+        self.schemas.insert(name.to_string(), fields);
+        Ok(())
+    }
+
+    ///
+    /// # Function
+    /// Learns the schema if the entry is a schema topic, or fills in `decoded` (and the struct type) if it is a struct topic
+    /// whose schema is known. Anything else is left as it is, so the raw bytes are always kept.
+    ///
+    pub fn decode(&mut self, entree: &mut TableEntree) {
+        if let Some(name) = entree.topic.strip_prefix(SCHEMA_PREFIX) {
+            let schema = match &entree.value {
+                TableValue::Raw(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                TableValue::String(schema) => schema.clone(),
+                _ => return,
+            };
+            if let Err(err) = self.add_schema(name, &schema) {
+                println!("Invalid struct schema for {}: {}", name, err);
+            }
+            return;
+        }
+
+        let r#type = if entree.value_type.starts_with(TYPE_PREFIX) {
+            entree.value_type.clone()
+        } else if let Some(r#type) = self
+            .types
+            .get(&(entree.source.clone(), entree.topic.clone()))
+        {
+            r#type.clone()
+        } else {
+            return;
+        };
+        let TableValue::Raw(bytes) = &entree.value else {
+            return;
+        };
+
+        let decoded = match r#type[TYPE_PREFIX.len()..].strip_suffix("[]") {
+            Some(name) => self.decode_array(name, bytes),
+            None => self.decode_struct(&r#type[TYPE_PREFIX.len()..], bytes),
+        };
+        if decoded.is_some() {
+            entree.value_type = r#type;
+            entree.decoded = decoded;
+        }
+    }
+
+    ///
+    /// # Function
+    /// Decodes one struct. The data has to be exactly the size of the struct.
+    ///
+    pub fn decode_struct(&self, name: &str, bytes: &[u8]) -> Option<JsonValue> {
+        if self.size(name, 0)? != bytes.len() {
+            return None;
+        }
+
+        self.read_struct(name, bytes, 0)
+    }
+
+    ///
+    /// # Function
+    /// Decodes an array of structs (the `struct:Name[]` types), which are the structs right after each other.
+    ///
+    pub fn decode_array(&self, name: &str, bytes: &[u8]) -> Option<JsonValue> {
+        let size = self.size(name, 0)?;
+        if size == 0 || !bytes.len().is_multiple_of(size) {
+            return None;
+        }
+
+        bytes
+            .chunks(size)
+            .map(|chunk| self.read_struct(name, chunk, 0))
+            .collect::<Option<Vec<_>>>()
+            .map(JsonValue::Array)
+    }
+
+    fn size(&self, name: &str, depth: usize) -> Option<usize> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let mut size = 0;
+        let mut bit_field: Option<BitField> = None;
+        for field in self.schemas.get(name)? {
+            if let Some(bits) = field.bits {
+                let bytes = field.r#type.size()?;
+                if bit_field
+                    .as_mut()
+                    .is_some_and(|unit| unit.fits(bytes, bits))
+                {
+                    bit_field.as_mut()?.used += bits;
+                } else {
+                    size += bytes;
+                    bit_field = Some(BitField { bytes, used: bits });
+                }
+                continue;
+            }
+            bit_field = None;
+
+            let element = match &field.r#type {
+                FieldType::Struct(child) => self.size(child, depth + 1)?,
+                r#type => r#type.size()?,
+            };
+            size += element * field.array.unwrap_or(1);
+        }
+
+        Some(size)
+    }
+
+    fn read_struct(&self, name: &str, bytes: &[u8], depth: usize) -> Option<JsonValue> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let mut object = Map::new();
+        let mut offset = 0;
+        let mut bit_field: Option<BitField> = None;
+        for field in self.schemas.get(name)? {
+            if let Some(bits) = field.bits {
+                let bytes_used = field.r#type.size()?;
+                if !bit_field
+                    .as_ref()
+                    .is_some_and(|unit| unit.fits(bytes_used, bits))
+                {
+                    offset += bytes_used;
+                    bit_field = Some(BitField {
+                        bytes: bytes_used,
+                        used: 0,
+                    });
+                }
+                let unit = bit_field.as_mut()?;
+                let storage = read_unsigned(bytes.get(offset - unit.bytes..offset)?);
+                let value = (storage >> unit.used) & mask(bits);
+                unit.used += bits;
+
+                let value = match field.r#type {
+                    FieldType::Bool => JsonValue::Bool(value != 0),
+                    FieldType::Integer { signed: true, .. } => {
+                        JsonValue::from(sign_extend(value, bits))
+                    }
+                    _ => JsonValue::from(value),
+                };
+                object.insert(field.name.clone(), value);
+                continue;
+            }
+            bit_field = None;
+
+            let element = match &field.r#type {
+                FieldType::Struct(child) => self.size(child, depth + 1)?,
+                r#type => r#type.size()?,
+            };
+            let length = field.array.unwrap_or(1);
+            let data = bytes.get(offset..offset + element * length)?;
+            offset += element * length;
+
+            let value = match (&field.r#type, field.array) {
+                (FieldType::Char, Some(_)) => JsonValue::String(
+                    String::from_utf8_lossy(data)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                ),
+                (r#type, Some(_)) => JsonValue::Array(
+                    data.chunks(element)
+                        .map(|chunk| self.read_field(r#type, chunk, depth))
+                        .collect::<Option<Vec<_>>>()?,
+                ),
+                (r#type, None) => self.read_field(r#type, data, depth)?,
+            };
+            object.insert(field.name.clone(), value);
+        }
+
+        Some(JsonValue::Object(object))
+    }
+
+    fn read_field(&self, r#type: &FieldType, bytes: &[u8], depth: usize) -> Option<JsonValue> {
+        Some(match r#type {
+            FieldType::Bool => JsonValue::Bool(bytes[0] != 0),
+            FieldType::Char => JsonValue::String((bytes[0] as char).to_string()),
+            FieldType::Integer {
+                bytes: size,
+                signed,
+            } => {
+                let value = read_unsigned(bytes);
+                if *signed {
+                    JsonValue::from(sign_extend(value, *size as u32 * 8))
+                } else {
+                    JsonValue::from(value)
+                }
+            }
+            FieldType::Float => JsonValue::from(f32::from_le_bytes(bytes.try_into().ok()?) as f64),
+            FieldType::Double => JsonValue::from(f64::from_le_bytes(bytes.try_into().ok()?)),
+            FieldType::Struct(name) => self.read_struct(name, bytes, depth + 1)?,
+        })
+    }
+}
+
+/// The integer that the current run of bit-fields is packed into
+#[derive(Debug)]
+struct BitField {
+    bytes: usize,
+    used: u32,
+}
+
+impl BitField {
+    fn fits(&self, bytes: usize, bits: u32) -> bool {
+        self.bytes == bytes && self.used + bits <= bytes as u32 * 8
+    }
+}
+
+fn read_unsigned(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+fn mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    if bits == 0 || bits >= 64 {
+        return value as i64;
+    }
+
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose_decoder() -> StructDecoder {
+        let mut decoder = StructDecoder::default();
+        decoder
+            .add_schema("Pose2d", "Translation2d translation;Rotation2d rotation")
+            .unwrap();
+        decoder
+            .add_schema("Translation2d", "double x;double y")
+            .unwrap();
+        decoder.add_schema("Rotation2d", "double value").unwrap();
+        decoder
+    }
+
+    fn doubles(values: &[f64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_struct() {
+        let decoder = pose_decoder();
+        assert_eq!(
+            decoder.decode_struct("Pose2d", &doubles(&[1.0, 2.0, 0.5])),
+            Some(
+                serde_json::json!({"translation": {"x": 1.0, "y": 2.0}, "rotation": {"value": 0.5}})
+            )
+        );
+        // wrong size or unknown schema
+        assert_eq!(decoder.decode_struct("Pose2d", &doubles(&[1.0, 2.0])), None);
+        assert_eq!(decoder.decode_struct("Pose3d", &doubles(&[1.0])), None);
+    }
+
+    #[test]
+    fn test_struct_array_entree() {
+        let mut decoder = StructDecoder::default();
+        let mut schema = TableEntree::with_type(
+            "/.schema/struct:SwerveModuleState".to_string(),
+            "structschema".to_string(),
+            TableValue::Raw(b"double speed;Rotation2d angle".to_vec()),
+            0,
+        );
+        decoder.decode(&mut schema);
+        decoder.add_schema("Rotation2d", "double value").unwrap();
+        assert_eq!(schema.decoded, None);
+
+        // the type is only known from the announcement, the value came in as raw
+        decoder.set_type(None, "/Swerve/States", "struct:SwerveModuleState[]");
+        let mut entree = TableEntree::new(
+            "/Swerve/States".to_string(),
+            TableValue::Raw(doubles(&[1.0, 0.1, 2.0, 0.2])),
+            5,
+        );
+        decoder.decode(&mut entree);
+        assert_eq!(entree.value_type, "struct:SwerveModuleState[]");
+        assert_eq!(
+            entree.decoded,
+            Some(serde_json::json!([
+                {"speed": 1.0, "angle": {"value": 0.1}},
+                {"speed": 2.0, "angle": {"value": 0.2}}
+            ]))
+        );
+        assert_eq!(
+            entree.value,
+            TableValue::Raw(doubles(&[1.0, 0.1, 2.0, 0.2]))
+        );
+
+        // another connection with the same topic has its own type
+        decoder.set_type(Some("sim"), "/Swerve/States", "raw");
+        let mut entree = TableEntree::new(
+            "/Swerve/States".to_string(),
+            TableValue::Raw(doubles(&[1.0, 0.1, 2.0, 0.2])),
+            5,
+        );
+        entree.source = Some("sim".to_string());
+        decoder.decode(&mut entree);
+        assert_eq!(entree.value_type, "raw");
+        assert_eq!(entree.decoded, None);
+    }
+
+    #[test]
+    fn test_integers_bit_fields_and_arrays() {
+        let mut decoder = StructDecoder::default();
+        decoder
+            .add_schema(
+                "Status",
+                "enum {off=0, on=1} int8 mode;int16 current;uint8 flag:1;int8 level:3;bool ok:1;char name[4];float values[2]",
+            )
+            .unwrap();
+
+        let mut bytes = vec![1, 0xfe, 0xff]; // mode 1, current -2
+        bytes.push(0b11011); // ok true, level -3 (0b101), flag 1
+        bytes.extend(b"ab\0\0");
+        bytes.extend(1.5f32.to_le_bytes());
+        bytes.extend((-2.0f32).to_le_bytes());
+
+        assert_eq!(
+            decoder.decode_struct("Status", &bytes),
+            Some(serde_json::json!({
+                "mode": 1,
+                "current": -2,
+                "flag": 1,
+                "level": -3,
+                "ok": true,
+                "name": "ab",
+                "values": [1.5, -2.0]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        assert!(parse_schema("double").is_err());
+        assert!(parse_schema("double x[abc]").is_err());
+        assert!(parse_schema("double x:3").is_err());
+        assert!(parse_schema("enum {a=1 int8 x").is_err());
+        assert_eq!(parse_schema(" double x ; ").unwrap().len(), 1);
+
+        // a struct that contains itself has no size
+        let mut decoder = StructDecoder::default();
+        decoder.add_schema("Loop", "Loop inner").unwrap();
+        assert_eq!(decoder.decode_struct("Loop", &[]), None);
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::{
+    database::structs::table_entree::TableEntree,
+    network_table_bridge::{
+        connection_status::ConnectionStatus,
+        topic_filter::TopicFilter,
+        writer::{WriteError, WriteRequest},
+    },
+};
+
+pub mod websocket;
+
+///
+/// # Function
+/// A message that is sent to a live client. It is written as `{"type": "...", "data": ...}` so the frontend can switch on `type`.
+///
+/// # Variants
+/// - `Entree`: A new entry that matches the patterns the client subscribed to
+/// - `Dropped`: The amount of entries that were skipped because the client was not reading fast enough
+/// - `Status`: The network table connection changed. Sent to every client (subscribed or not), and once right after connecting
+/// - `WriteResult`: The answer to a `write` request of this client
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum LiveMessage {
+    Entree(TableEntree),
+    Dropped(u64),
+    Status(ConnectionStatus),
+    WriteResult {
+        topic: String,
+        result: Result<(), WriteError>,
+    },
+}
+
+///
+/// # Function
+/// A message that a live client can send.
+///
+/// # Variants
+/// - `Subscribe`: Start (or replace) the subscription. `patterns` and `deny` use the same format as the topic allowlist / denylist. An empty `patterns` list means every topic
+/// - `Unsubscribe`: Stop receiving entries
+/// - `Write`: Write a value to the network table, same as `POST /network-table/write`. Answered with a `write_result` message
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveRequest {
+    Subscribe {
+        patterns: Vec<String>,
+        #[serde(default)]
+        deny: Vec<String>,
+    },
+    Unsubscribe,
+    Write {
+        data: WriteRequest,
+    },
+}
+
+#[derive(Debug)]
+struct LiveClient {
+    filter: Mutex<Option<TopicFilter>>,
+    sender: mpsc::Sender<LiveMessage>,
+    dropped: AtomicU64,
+}
+
+///
+/// # Function
+/// Sends every ingested entry to the live clients that subscribed to it. Every client has its own bounded buffer, if a client
+/// does not read fast enough its buffer fills up and new entries are dropped for that client only. Publishing never waits.
+///
+#[derive(Debug, Clone)]
+pub struct LiveFeed {
+    clients: Arc<Mutex<Vec<Arc<LiveClient>>>>,
+    /// The last status of every network table connection, by name
+    last_status: Arc<Mutex<BTreeMap<String, ConnectionStatus>>>,
+    client_buffer: usize,
+}
+
+///
+/// # Function
+/// The receiving side of one live client. Once this is dropped the client stops counting as connected and is removed from the feed.
+///
+#[derive(Debug)]
+pub struct LiveSubscription {
+    client: Arc<LiveClient>,
+    receiver: mpsc::Receiver<LiveMessage>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl LiveFeed {
+    ///
+    /// # Function
+    /// Makes a new feed without any clients.
+    ///
+    /// # Parameters
+    /// - `client_buffer`: How many messages can wait for each client before new ones are dropped
+    ///
+    pub fn new(client_buffer: usize) -> Self {
+        Self {
+            clients: Arc::new(Mutex::new(Vec::new())),
+            last_status: Arc::new(Mutex::new(BTreeMap::new())),
+            client_buffer: client_buffer.max(1),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Adds a new client. It gets the last status of every connection right away, but no entries until `LiveSubscription::set_filter` is called.
+    ///
+    pub fn subscribe(&self) -> LiveSubscription {
+        let (sender, receiver) = mpsc::channel(self.client_buffer);
+        if let Ok(last_status) = self.last_status.lock() {
+            for status in last_status.values() {
+                let _ = sender.try_send(LiveMessage::Status(status.clone()));
+            }
+        }
+
+        let client = Arc::new(LiveClient {
+            filter: Mutex::new(None),
+            sender,
+            dropped: AtomicU64::new(0),
+        });
+
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.push(client.clone());
+        }
+
+        LiveSubscription { client, receiver }
+    }
+
+    ///
+    /// # Function
+    /// Sends an entry to every client whose filter matches its topic. Clients that went away are removed.
+    ///
+    pub fn publish(&self, entree: &TableEntree) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+
+        clients.retain(|client| !client.sender.is_closed());
+        for client in clients.iter() {
+            let matches = match client.filter.lock() {
+                Ok(filter) => filter
+                    .as_ref()
+                    .is_some_and(|filter| filter.matches(&entree.topic)),
+                Err(_) => false,
+            };
+            if !matches {
+                continue;
+            }
+
+            if let Err(TrySendError::Full(_)) =
+                client.sender.try_send(LiveMessage::Entree(entree.clone()))
+            {
+                client.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    ///
+    /// # Function
+    /// Sends the status of a network table connection to every client and keeps it for clients that connect later.
+    ///
+    pub fn publish_status(&self, status: ConnectionStatus) {
+        if let Ok(mut last_status) = self.last_status.lock() {
+            last_status.insert(status.source.clone(), status.clone());
+        }
+
+        let Ok(clients) = self.clients.lock() else {
+            return;
+        };
+        for client in clients.iter() {
+            if let Err(TrySendError::Full(_)) =
+                client.sender.try_send(LiveMessage::Status(status.clone()))
+            {
+                client.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    ///
+    /// # Function
+    /// Gets the amount of clients that are currently connected.
+    ///
+    pub fn client_count(&self) -> usize {
+        self.clients
+            .lock()
+            .map(|clients| {
+                clients
+                    .iter()
+                    .filter(|client| !client.sender.is_closed())
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl LiveSubscription {
+    ///
+    /// # Function
+    /// Changes which topics this client receives. `None` stops the client from receiving entries.
+    ///
+    pub fn set_filter(&self, filter: Option<TopicFilter>) {
+        if let Ok(mut current) = self.client.filter.lock() {
+            *current = filter;
+        }
+    }
+
+    ///
+    /// # Function
+    /// Applies a request that the client sent.
+    ///
+    pub fn apply(&self, request: LiveRequest) {
+        match request {
+            LiveRequest::Subscribe { patterns, deny } => {
+                self.set_filter(Some(TopicFilter::new(patterns, deny)))
+            }
+            LiveRequest::Unsubscribe => self.set_filter(None),
+            LiveRequest::Write { .. } => {} // needs the writer, handled by the WebSocket server
+        }
+    }
+
+    ///
+    /// # Function
+    /// Waits for the next message for this client. If entries were dropped since the last call, a `Dropped` message is returned first.
+    ///
+    /// # Returns
+    /// `None` if the feed was dropped
+    ///
+    pub async fn recv(&mut self) -> Option<LiveMessage> {
+        let dropped = self.client.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            return Some(LiveMessage::Dropped(dropped));
+        }
+
+        self.receiver.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::structs::table_value::TableValue;
+
+    use super::*;
+
+    fn entree(topic: &str, timestamp: u64) -> TableEntree {
+        TableEntree::new(topic.to_string(), TableValue::Int(1), timestamp)
+    }
+
+    #[tokio::test]
+    async fn test_only_matching_topics_are_sent() {
+        let feed = LiveFeed::new(10);
+        let mut subscription = feed.subscribe();
+
+        feed.publish(&entree("/Drive/Speed", 0)); // no filter yet
+        subscription.apply(LiveRequest::Subscribe {
+            patterns: vec!["/Drive/*".to_string()],
+            deny: vec![],
+        });
+        feed.publish(&entree("/Arm/Angle", 1));
+        feed.publish(&entree("/Drive/Speed", 2));
+
+        assert_eq!(
+            subscription.recv().await,
+            Some(LiveMessage::Entree(entree("/Drive/Speed", 2)))
+        );
+        assert!(subscription.receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_drops() {
+        let feed = LiveFeed::new(2);
+        let mut slow = feed.subscribe();
+        slow.set_filter(Some(TopicFilter::default()));
+
+        for i in 0..5 {
+            feed.publish(&entree("/Drive/Speed", i));
+        }
+
+        assert_eq!(slow.recv().await, Some(LiveMessage::Dropped(3)));
+        assert_eq!(
+            slow.recv().await,
+            Some(LiveMessage::Entree(entree("/Drive/Speed", 0)))
+        );
+        assert_eq!(
+            slow.recv().await,
+            Some(LiveMessage::Entree(entree("/Drive/Speed", 1)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closed_clients_are_removed() {
+        let feed = LiveFeed::new(2);
+        let subscription = feed.subscribe();
+        assert_eq!(feed.client_count(), 1);
+
+        drop(subscription);
+        feed.publish(&entree("/Drive/Speed", 0));
+        assert_eq!(feed.client_count(), 0);
+        assert!(feed.clients.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_status_is_sent_to_everyone() {
+        let feed = LiveFeed::new(10);
+        let mut before = feed.subscribe();
+        let status = ConnectionStatus {
+            source: "robot".to_string(),
+            address: "127.0.0.1:5810".to_string(),
+            ..Default::default()
+        };
+        let sim = ConnectionStatus {
+            source: "sim".to_string(),
+            address: "127.0.0.1:5811".to_string(),
+            ..Default::default()
+        };
+
+        feed.publish_status(status.clone());
+        feed.publish_status(sim.clone());
+        let mut after = feed.subscribe();
+
+        assert_eq!(
+            before.recv().await,
+            Some(LiveMessage::Status(status.clone()))
+        );
+        // a client that connects later gets the last status of every connection
+        assert_eq!(after.recv().await, Some(LiveMessage::Status(status)));
+        assert_eq!(after.recv().await, Some(LiveMessage::Status(sim)));
+    }
+
+    #[test]
+    fn test_message_format() {
+        assert_eq!(
+            serde_json::to_string(&LiveMessage::Dropped(3)).unwrap(),
+            r#"{"type":"dropped","data":3}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<LiveRequest>(r#"{"type":"subscribe","patterns":["/Drive/"]}"#)
+                .unwrap(),
+            LiveRequest::Subscribe {
+                patterns: vec!["/Drive/".to_string()],
+                deny: vec![]
+            }
+        );
+    }
+}
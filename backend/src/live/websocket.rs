@@ -0,0 +1,178 @@
+use futures::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{LiveFeed, LiveMessage, LiveRequest};
+use crate::network_table_bridge::writer::NetworkTableWriter;
+
+///
+/// # Function
+/// Accepts WebSocket clients and streams the live feed to them. Every client gets its own task, so a slow client only
+/// slows down itself. This never returns unless the listener fails.
+///
+/// # Protocol
+/// - The client sends `{"type": "subscribe", "patterns": ["/SmartDashboard/Drive/*"], "deny": []}` to choose the topics it wants.
+///   Sending it again replaces the old subscription. `{"type": "unsubscribe"}` stops the stream.
+/// - The client sends `{"type": "write", "data": {"topic": "/Tuning/kP", "type": "double", "value": 0.05}}` to write a value to the
+///   network table. The server answers with `{"type": "write_result", "data": {"topic": "/Tuning/kP", "result": {"Ok": null}}}`.
+/// - The server sends `{"type": "entree", "data": TableEntree}` for every new entry and `{"type": "dropped", "data": 12}` when
+///   entries had to be skipped because the client was too slow.
+///
+/// # Parameters
+/// - `listener`: The TCP listener the clients connect to
+/// - `feed`: The feed that the entries come from
+/// - `writer`: Used for the `write` requests
+///
+pub async fn serve(listener: TcpListener, feed: LiveFeed, writer: NetworkTableWriter) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(handle_client(stream, feed.clone(), writer.clone()));
+    }
+}
+
+async fn handle_client(stream: TcpStream, feed: LiveFeed, writer: NetworkTableWriter) {
+    let remote = stream.peer_addr().ok().map(|remote| remote.to_string());
+    let Ok(websocket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut write, mut read) = websocket.split();
+    let mut subscription = feed.subscribe();
+    println!("Live client connected ({} connected)", feed.client_count());
+
+    loop {
+        tokio::select! {
+            incoming = read.next() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<LiveRequest>(&text) {
+                    Ok(LiveRequest::Write { data }) => {
+                        let topic = data.topic.clone();
+                        let result = writer.write(data, "websocket", remote.clone()).await;
+                        let Ok(text) = serde_json::to_string(&LiveMessage::WriteResult { topic, result }) else {
+                            continue;
+                        };
+                        if write.send(Message::Text(text)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(request) => subscription.apply(request),
+                    Err(err) => println!("Invalid live request: {}", err),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {} // pings are answered by tungstenite
+            },
+            outgoing = subscription.recv() => {
+                let Some(message) = outgoing else {
+                    break;
+                };
+                let Ok(text) = serde_json::to_string(&message) else {
+                    continue;
+                };
+                if write.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    drop(subscription);
+    println!(
+        "Live client disconnected ({} connected)",
+        feed.client_count()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+    use crate::{
+        database::{
+            structs::{table_entree::TableEntree, table_value::TableValue},
+            SQLiteDatabase,
+        },
+        network_table_bridge::{topic_filter::TopicFilter, writer::WriteError},
+    };
+
+    use super::*;
+
+    type TestClient = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn start_server(feed: &LiveFeed) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let writer = NetworkTableWriter::new(
+            TopicFilter::from_lists("/Tuning/", ""),
+            Arc::new(Mutex::new(SQLiteDatabase::new("test.db", 2).unwrap())),
+        );
+        tokio::spawn(serve(listener, feed.clone(), writer));
+        address
+    }
+
+    async fn next_message(client: &mut TestClient) -> LiveMessage {
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_subscribe_and_receive() {
+        let feed = LiveFeed::new(16);
+        let address = start_server(&feed).await;
+
+        let (mut client, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        client
+            .send(Message::Text(
+                r#"{"type":"subscribe","patterns":["/Drive/"]}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+
+        // wait until the server has read the subscription
+        while feed.client_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let wanted = TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(1.5), 10);
+        feed.publish(&TableEntree::new(
+            "/Arm/Angle".to_string(),
+            TableValue::Double(0.0),
+            5,
+        ));
+        feed.publish(&wanted);
+
+        assert_eq!(next_message(&mut client).await, LiveMessage::Entree(wanted));
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_write() {
+        let feed = LiveFeed::new(16);
+        let address = start_server(&feed).await;
+
+        let (mut client, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        client
+            .send(Message::Text(
+                r#"{"type":"write","data":{"topic":"/Drive/Speed","type":"double","value":1}}"#
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            next_message(&mut client).await,
+            LiveMessage::WriteResult {
+                topic: "/Drive/Speed".to_string(),
+                result: Err(WriteError::TopicNotWritable)
+            }
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     env,
+    str::FromStr,
     sync::{Arc, Mutex},
 };
 
@@ -11,10 +12,33 @@ extern crate rocket;
 extern crate dotenv;
 
 mod database;
+mod ingest;
+mod live;
 mod network_table_bridge;
 mod server;
+mod source;
 
 use dotenv::dotenv;
+use ingest::{
+    array_elements::ArrayElements, deduplicator::DeduplicationConfig, Ingest, IngestConfig,
+};
+use live::LiveFeed;
+use network_table_bridge::{
+    connection_config::ConnectionConfig,
+    connection_status::{ConnectionTracker, ConnectionTrackers},
+    protocol_mode::ProtocolMode,
+    reconnect_policy::ReconnectPolicy,
+    robot_address::RobotAddress,
+    subscription_group::SubscriptionGroup,
+    topic_filter::TopicFilter,
+    writer::NetworkTableWriter,
+    BridgeConfig, NetworkTableSource,
+};
+use source::{
+    generator::{GeneratorConfig, GeneratorSource},
+    nt4_server::{Nt4ServerConfig, Nt4ServerSource},
+    Source,
+};
 
 ///
 /// # Function
@@ -47,44 +71,119 @@ async fn main() {
             .red()
         );
 
-        return ();
+        return;
     }
 
     let database = database::SQLiteDatabase::new(
         &env::var("DATABASE_PATH").unwrap(),
         env::var("DATABASE_MIN_TIME_AFTER_UPDATE")
             .unwrap()
-            .parse()
-            .unwrap(),
+            .parse::<u64>()
+            .unwrap()
+            * 1000, // the env is in milliseconds, the database works in microseconds
     );
 
     if database.is_err() {
         println!("{}", "Failed to initialize database. Shutting down.".red());
-        return ();
+        return;
     }
 
     let database = Arc::new(Mutex::new(database.unwrap())); // Arc -> allows multiple pointers to one instance in multiprocessing environments, Mutex -> allows writing safely in a multiprocessing environment
-    let server_task =
-        server::rocket_launch(&database, env::var("SERVER_PORT").unwrap().parse().unwrap()); // get the rocket server start instance
-    let table_task = local_set.run_until(async move /* move essentially means that all variables used inside this async function are owned by this async function are moved from the outside */ {
-        // get the network table start instance
-        network_table_bridge::begin_network_table(
+
+    // The bridge only pushes the data into a queue, this task writes it to the database in batches
+    let live_feed = LiveFeed::new(read_env_or("WEBSOCKET_CLIENT_BUFFER", 1024));
+    let (ingest, ingest_writer) = Ingest::new(get_ingest_config(), live_feed.clone());
+    let ingest_writer = match get_deduplication_config() {
+        Some(config) => ingest_writer.with_deduplication(config),
+        None => ingest_writer,
+    };
+    let ingest_writer =
+        match ArrayElements::from_list(&env::var("RECORD_ARRAY_ELEMENTS").unwrap_or_default()) {
+            Some(array_elements) => ingest_writer.with_array_elements(array_elements),
+            None => ingest_writer,
+        };
+    let ingest_task = tokio::spawn(ingest_writer.run(database.clone()));
+    let shutdown_ingest = ingest.clone(); // used to end the recording session when shutting down
+
+    // Nothing can be written to the network table unless NETWORK_TABLE_WRITABLE_TOPICS is set
+    let writer = NetworkTableWriter::new(
+        TopicFilter::from_lists(
+            &env::var("NETWORK_TABLE_WRITABLE_TOPICS").unwrap_or_default(),
+            "",
+        ),
+        database.clone(),
+    );
+
+    let server_port: u16 = env::var("SERVER_PORT").unwrap().parse().unwrap();
+    let websocket_port = read_env_or("WEBSOCKET_PORT", server_port.saturating_add(1));
+    match tokio::net::TcpListener::bind(("0.0.0.0", websocket_port)).await {
+        Ok(listener) => {
+            tokio::spawn(live::websocket::serve(
+                listener,
+                live_feed.clone(),
+                writer.clone(),
+            ));
+        }
+        Err(err) => println!(
+            "{}",
+            format!("Failed to start the live WebSocket server: {}", err).red()
+        ),
+    }
+
+    let connection = ConnectionTracker::new(
+        get_network_table_name(),
+        format!(
+            "{}:{}",
             env::var("NETWORK_TABLE_IP").unwrap(),
-            env::var("NETWORK_TABLE_PORT").unwrap().parse().unwrap(),
-            env::var("TIME_BETWEEN_RECONNECT_ATTEMPTS")
-                .unwrap()
-                .parse()
-                .unwrap(),
-            Box::new(move |data, database| network_table_bridge::write_all(data, database.clone())),
-            database,
-        )
-        .await // awaiting the network table start instance
-    });
+            env::var("NETWORK_TABLE_PORT").unwrap()
+        ),
+        live_feed.clone(),
+    );
+
+    // the other network table connections, each with its own status
+    let connections: Vec<(ConnectionConfig, ConnectionTracker)> = get_connections()
+        .into_iter()
+        .map(|config| {
+            let tracker = ConnectionTracker::new(
+                config.name.clone(),
+                format!("{}:{}", config.address, config.port),
+                live_feed.clone(),
+            );
+            (config, tracker)
+        })
+        .collect();
+    let trackers = ConnectionTrackers(
+        std::iter::once(connection.clone())
+            .chain(connections.iter().map(|(_, tracker)| tracker.clone()))
+            .collect(),
+    );
+
+    let server_task = server::rocket_launch(
+        &database,
+        ingest.counters(),
+        connection.clone(),
+        trackers,
+        writer.clone(),
+        server_port,
+    ); // get the rocket server start instance
+
+    // every source (the network table and anything else in the list) pushes its data into the ingestion pipeline
+    let sources = get_sources(connection, connections, writer).await;
+    let table_task = async {
+        local_set.run_until(async move /* move essentially means that all variables used inside this async function are owned by this async function are moved from the outside */ {
+            source::run_all(sources, ingest).await // awaiting every source
+        }).await;
+        // the network table gave up reconnecting (its status says so), the recorded data can still be looked at
+        println!(
+            "{}",
+            "Every data source finished, the server keeps running until it is shut down (Ctrl+C)"
+                .red()
+        );
+        std::future::pending::<()>().await
+    };
 
     tokio::select! { // Essentially allows you to await multiple tasks at the same time (usually not possible)
-        _ = table_task => {
-            println!("{}", "Table task shut down!".red());
-        }
+        _ = table_task => {} // never finishes
         _ = server_task => {
             println!("{}", "Backend server task shut down!".red());
         }
@@ -92,7 +191,7 @@ async fn main() {
             // https://docs.rs/tokio/latest/tokio/signal/fn.ctrl_c.html
             signal::ctrl_c()
                 .await
-                .expect(&"Failed to listen for shutdown signal (Ctrl+C)".red());
+                .unwrap_or_else(|_| panic!("{}", "Failed to listen for shutdown signal (Ctrl+C)".red()));
 
             println!("Received shutdown signal. Shutting down...");
         } => {
@@ -100,9 +199,252 @@ async fn main() {
         }
     };
 
+    // stop the sources, then end the session after everything they sent is written and wait for the writer to finish
+    drop(local_set);
+    shutdown_ingest.end_session().await;
+    shutdown_ingest.stop().await;
+    let _ = ingest_task.await;
+
     println!("Shut down complete.");
+}
+
+///
+/// # Function
+/// Reads the optional settings of the ingestion pipeline from the env. Anything that is not set (or is not a number) uses the default.
+///
+fn get_ingest_config() -> IngestConfig {
+    let default = IngestConfig::default();
+
+    IngestConfig {
+        channel_capacity: read_env_or("INGEST_CHANNEL_CAPACITY", default.channel_capacity),
+        max_batch_size: read_env_or("INGEST_MAX_BATCH_SIZE", default.max_batch_size),
+        max_batch_delay: read_env_or("INGEST_MAX_BATCH_DELAY", default.max_batch_delay),
+    }
+}
+
+///
+/// # Function
+/// Reads the change-only recording settings from the env.
+///
+/// # Returns
+/// `None` if `RECORD_CHANGES_ONLY` is not `true`
+///
+fn get_deduplication_config() -> Option<DeduplicationConfig> {
+    if !read_env_or("RECORD_CHANGES_ONLY", false) {
+        return None;
+    }
 
-    return ();
+    let default = DeduplicationConfig::default();
+    Some(DeduplicationConfig {
+        deadbands: DeduplicationConfig::parse_deadbands(
+            &env::var("RECORD_DEADBANDS").unwrap_or_default(),
+        ),
+        keyframe_interval: read_env_or(
+            "RECORD_KEYFRAME_INTERVAL",
+            default.keyframe_interval / 1000,
+        )
+        .saturating_mul(1000),
+    })
+}
+
+///
+/// # Function
+/// Makes the sources listed in `DATA_SOURCES` (comma separated, `network-table` by default). Unknown names are reported and skipped.
+/// `network-table` is the main network table connection and every connection of `NETWORK_TABLE_CONNECTIONS`. `nt4-server`
+/// is the embedded NT4 server, it is skipped (and reported) if its port can not be used.
+///
+/// # Parameters
+/// - `connection`: The connection status of the main network table connection
+/// - `connections`: The other network table connections and their status
+/// - `writer`: Gets the client of the main network table connection so the dashboard can write values
+///
+async fn get_sources(
+    connection: ConnectionTracker,
+    connections: Vec<(ConnectionConfig, ConnectionTracker)>,
+    writer: NetworkTableWriter,
+) -> Vec<Box<dyn Source>> {
+    let names = env::var("DATA_SOURCES").unwrap_or_else(|_| "network-table".to_string());
+    let mut sources: Vec<Box<dyn Source>> = Vec::new();
+
+    for name in names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match name {
+            "network-table" => {
+                sources.push(Box::new(NetworkTableSource::new(
+                    BridgeConfig {
+                        name: get_network_table_name(),
+                        owns_sessions: true,
+                        robot_address: RobotAddress::parse(&env::var("NETWORK_TABLE_IP").unwrap()),
+                        port: env::var("NETWORK_TABLE_PORT").unwrap().parse().unwrap(),
+                        protocol: get_protocol_mode(),
+                        v3_port: read_env_or("NETWORK_TABLE_V3_PORT", 1735),
+                        reconnect_policy: get_reconnect_policy(),
+                        topic_filter: TopicFilter::from_lists(
+                            &env::var("NETWORK_TABLE_TOPIC_ALLOWLIST").unwrap_or_default(),
+                            &env::var("NETWORK_TABLE_TOPIC_DENYLIST").unwrap_or_default(),
+                        ),
+                        subscription_groups: get_subscription_groups(),
+                    },
+                    Box::new(network_table_bridge::write_all),
+                    connection.clone(),
+                    Some(writer.clone()),
+                )));
+                for (config, tracker) in &connections {
+                    sources.push(Box::new(NetworkTableSource::new(
+                        config.bridge_config(get_reconnect_policy()),
+                        Box::new(network_table_bridge::write_all),
+                        tracker.clone(),
+                        None,
+                    )));
+                }
+            }
+            "generator" => {
+                let default = GeneratorConfig::default();
+                sources.push(Box::new(GeneratorSource::new(GeneratorConfig {
+                    rate: read_env_or("GENERATOR_RATE", default.rate),
+                    slow_rate: read_env_or("GENERATOR_SLOW_RATE", default.slow_rate),
+                    seed: read_env_or("GENERATOR_SEED", default.seed),
+                })))
+            }
+            "nt4-server" => {
+                let default = Nt4ServerConfig::default();
+                let config = Nt4ServerConfig {
+                    name: env::var("NT4_SERVER_NAME")
+                        .ok()
+                        .filter(|name| !name.trim().is_empty())
+                        .unwrap_or(default.name),
+                    port: read_env_or("NT4_SERVER_PORT", default.port),
+                };
+                let port = config.port;
+                match Nt4ServerSource::bind(config).await {
+                    Ok(server) => sources.push(Box::new(server)),
+                    Err(err) => println!(
+                        "{}",
+                        format!("Failed to start the NT4 server on port {}: {}", port, err).red()
+                    ),
+                }
+            }
+            _ => println!("{}", format!("Unknown data source: {}", name).red()),
+        }
+    }
+
+    sources
+}
+
+///
+/// # Function
+/// Reads the subscription groups from the env. An invalid `NETWORK_TABLE_SUBSCRIPTION_GROUPS` is reported and ignored.
+///
+fn get_subscription_groups() -> Vec<SubscriptionGroup> {
+    SubscriptionGroup::parse_list(
+        &env::var("NETWORK_TABLE_SUBSCRIPTION_GROUPS").unwrap_or_default(),
+    )
+    .unwrap_or_else(|err| {
+        println!(
+            "Invalid NETWORK_TABLE_SUBSCRIPTION_GROUPS, using the default subscription: {}",
+            err
+        );
+        Vec::new()
+    })
+}
+
+///
+/// # Function
+/// Gets the name of the main network table connection (`NETWORK_TABLE_NAME`, `robot` by default).
+///
+fn get_network_table_name() -> String {
+    env::var("NETWORK_TABLE_NAME")
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "robot".to_string())
+}
+
+///
+/// # Function
+/// Reads the other network table connections from the env. An invalid `NETWORK_TABLE_CONNECTIONS` is reported and ignored,
+/// connections with a name that is already used are reported and skipped.
+///
+fn get_connections() -> Vec<ConnectionConfig> {
+    let connections =
+        ConnectionConfig::parse_list(&env::var("NETWORK_TABLE_CONNECTIONS").unwrap_or_default())
+            .unwrap_or_else(|err| {
+                println!(
+                    "Invalid NETWORK_TABLE_CONNECTIONS, only using the main connection: {}",
+                    err
+                );
+                Vec::new()
+            });
+
+    let mut names = vec![get_network_table_name()];
+    connections
+        .into_iter()
+        .filter(|config| {
+            if names.contains(&config.name) {
+                println!(
+                    "{}",
+                    format!(
+                        "The network table connection name {} is used twice, skipping it",
+                        config.name
+                    )
+                    .red()
+                );
+                return false;
+            }
+            names.push(config.name.clone());
+            true
+        })
+        .collect()
+}
+
+///
+/// # Function
+/// Reads the protocol mode from the env. An invalid `NETWORK_TABLE_PROTOCOL` is reported and `auto` is used.
+///
+fn get_protocol_mode() -> ProtocolMode {
+    env::var("NETWORK_TABLE_PROTOCOL")
+        .map(|mode| {
+            ProtocolMode::from_str(&mode).unwrap_or_else(|err| {
+                println!("Invalid NETWORK_TABLE_PROTOCOL, using auto: {}", err);
+                ProtocolMode::default()
+            })
+        })
+        .unwrap_or_default()
+}
+
+///
+/// # Function
+/// Reads the reconnect policy from the env. `TIME_BETWEEN_RECONNECT_ATTEMPTS` is the first delay, the rest is optional.
+///
+fn get_reconnect_policy() -> ReconnectPolicy {
+    let default = ReconnectPolicy::default();
+
+    ReconnectPolicy {
+        initial_delay: read_env_or("TIME_BETWEEN_RECONNECT_ATTEMPTS", default.initial_delay),
+        multiplier: read_env_or("RECONNECT_BACKOFF_MULTIPLIER", default.multiplier),
+        max_delay: read_env_or("RECONNECT_MAX_DELAY", default.max_delay),
+        jitter: read_env_or("RECONNECT_JITTER", default.jitter),
+        max_attempts: match read_env_or("RECONNECT_MAX_ATTEMPTS", 0) {
+            0 => None,
+            max_attempts => Some(max_attempts),
+        },
+    }
+}
+
+///
+/// # Function
+/// Reads an optional value (usually a number) from the env.
+///
+/// # Returns
+/// `default` if the env is not set or can not be parsed
+///
+fn read_env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
 }
 
 fn get_invalid_env_list() -> Vec<String> {
@@ -124,16 +466,16 @@ fn get_invalid_env_list() -> Vec<String> {
         }
     }
 
-    return invalid_envs;
+    invalid_envs
 }
 
 fn is_supposed_to_be_number(s: &str) -> bool {
-    let number_envs = vec![
+    let number_envs = [
         "DATABASE_MIN_TIME_AFTER_UPDATE",
         "NETWORK_TABLE_PORT",
         "TIME_BETWEEN_RECONNECT_ATTEMPTS",
         "SERVER_PORT",
     ];
 
-    return number_envs.contains(&s);
+    number_envs.contains(&s)
 }
@@ -1,108 +1,963 @@
-use std::{
-    future::Future,
-    net::{Ipv4Addr, SocketAddrV4},
-    str::FromStr,
-    sync::{Arc, Mutex},
+use std::{future::Future, net::SocketAddr};
+
+use connection_status::ConnectionTracker;
+use futures::future::LocalBoxFuture;
+use network_tables::v4::Type;
+use nt3::{Nt3Client, Nt3Event};
+use nt4::{Nt4Client, Nt4Event};
+use protocol_mode::{Protocol, ProtocolMode};
+use reconnect_policy::{Backoff, ReconnectPolicy, TokioClock};
+use robot_address::RobotAddress;
+use subscription_group::{SubscriptionGroup, SCHEMA_PREFIX};
+use topic_filter::TopicFilter;
+use writer::NetworkTableWriter;
+
+use crate::{
+    database::{
+        now_micros,
+        structs::{table_entree::TableEntree, topic_info::TopicInfo},
+    },
+    ingest::Ingest,
+    source::Source,
 };
 
-use network_tables::v4::{MessageData, SubscriptionOptions};
-use tokio::task::spawn_local;
+pub mod connection_config;
+pub mod connection_status;
+pub mod nt3;
+pub mod nt4;
+pub mod protocol_mode;
+pub mod reconnect_policy;
+pub mod robot_address;
+pub mod subscription_group;
+#[cfg(test)]
+pub mod test_server;
+pub mod topic_filter;
+pub mod writer;
+
+/// The name that the NT3 and NT4 servers show for the backend
+const IDENTITY: &str = "message-receiver-backend";
 
-use crate::database::{self, structs::table_entree::TableEntree, SQLiteDatabase};
+/// The function that the `NetworkTableSource` calls for every received entree
+pub type EntreeHandler = Box<dyn Fn(TableEntree, &Ingest)>;
 
+///
 /// # Function
-/// This function is used to connect to the network table and to keep the data in sync. It will periodically try to reconnect if it fails to connect.
-/// This is needed because, again, the "main" function would be too long if I were to put the contents of this function inside it
+/// Where and how the bridge connects to the network table.
 ///
-/// # Parameters
-/// - `url`: The url of the network table
-/// - `port`: The port of the network table
-/// - `time_between_reconnects`: The time between reconnect attempts in milliseconds
-/// - `function_to_call`: The function that will be called when a new message is received
-/// - `database`: The database that will be used to store the data
+/// # Fields
+/// - `name`: The name of the connection. Every entry and catalog topic of the connection is tagged with it (the `source` column)
+/// - `owns_sessions`: If connecting and disconnecting starts and ends the recording sessions. Only one connection should own them
+/// - `robot_address`: Where the network table is (team number, hostname or IP). Every candidate address is tried in order on each attempt
+/// - `port`: The port of the network table (NT4)
+/// - `protocol`: Which protocols to try. In `Auto` mode NT3 is tried when NT4 does not work
+/// - `v3_port`: The port of the network table when NT3 is used
+/// - `reconnect_policy`: How long to wait between connection attempts. The delay starts over once a subscription works
+/// - `topic_filter`: Decides which topics are subscribed to and recorded. Topics that do not match are never passed to the `function_to_call`
+/// - `subscription_groups`: The subscriptions to make, each with its own options. If it is empty, one subscription is made for the prefixes of `topic_filter` that gets every value
 ///
-/// # Returns
-/// A `tokio::task::JoinHandle<()>`. This is essentially something you can .await with tokio crate - that provides you with a way to wait for the task to finish in a local setting.
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    pub name: String,
+    pub owns_sessions: bool,
+    pub robot_address: RobotAddress,
+    pub port: u16,
+    pub protocol: ProtocolMode,
+    pub v3_port: u16,
+    pub reconnect_policy: ReconnectPolicy,
+    pub topic_filter: TopicFilter,
+    pub subscription_groups: Vec<SubscriptionGroup>,
+}
+
 ///
-/// # Await Usage
-/// Awaits in rust require you to "ping" the future every so often. This is the function of .await. However, the problem with this is that it freezes the whole current thread.
-/// To fix this, you can attach two different Futures together and wait on that (pinging both processes).
+/// # Function
+/// The network table as a `Source`. It connects to the network table and keeps the data in sync. It will try to reconnect (with an exponential backoff) if it fails to connect.
+/// Every connection is its own recording session if the source owns the sessions. More than one source can run at the same
+/// time (e.g. the robot, a simulator and a vision coprocessor), each with its own name, address, filters and reconnect state.
 ///
-/// # Await Documentation
-/// https://docs.rs/tokio/latest/tokio/task/struct.JoinHandle.html
+pub struct NetworkTableSource {
+    config: BridgeConfig,
+    function_to_call: EntreeHandler,
+    connection: ConnectionTracker,
+    writer: Option<NetworkTableWriter>,
+}
+
+impl NetworkTableSource {
+    ///
+    /// # Function
+    /// Makes the source. Nothing happens until it is run.
+    ///
+    /// # Parameters
+    /// - `config`: Where the network table is, how to reconnect and which topics to record
+    /// - `function_to_call`: The function that will be called when a new message is received. The message is already converted to a `TableEntree` with a 64-bit microsecond timestamp
+    /// - `connection`: Gets updated on every connection attempt, connection, disconnect and message so the dashboard can show the link health
+    /// - `writer`: Gets the client while it is connected so the dashboard can write values to the network table. `None` if the
+    ///   dashboard can not write to this connection
+    ///
+    pub fn new(
+        config: BridgeConfig,
+        function_to_call: EntreeHandler,
+        connection: ConnectionTracker,
+        writer: Option<NetworkTableWriter>,
+    ) -> Self {
+        Self {
+            config,
+            function_to_call,
+            connection,
+            writer,
+        }
+    }
+}
+
+impl Source for NetworkTableSource {
+    fn name(&self) -> String {
+        format!(
+            "NetworkTables {} ({:?})",
+            self.config.name, self.config.robot_address
+        )
+    }
+
+    fn run(self: Box<Self>, ingest: Ingest) -> LocalBoxFuture<'static, ()> {
+        Box::pin(begin_network_table(*self, ingest))
+    }
+}
+
+/// # Function
+/// The loop of the `NetworkTableSource`. It only returns if `max_attempts` of the reconnect policy is reached.
 ///
-pub fn begin_network_table(
-    url: String,
-    port: i32,
-    time_between_reconnects: u64,
-    function_to_call: Box<dyn Fn(MessageData, Arc<Mutex<SQLiteDatabase>>) -> ()>,
-    database: Arc<Mutex<SQLiteDatabase>>,
-) -> tokio::task::JoinHandle<()> {
-    spawn_local(async move {
-        println!("Starting NetworkTable Bridge");
-        let mut first = true;
-        loop {
-            if !first {
-                tokio::time::sleep(tokio::time::Duration::from_millis(time_between_reconnects))
-                    .await;
+/// # Parameters
+/// - `source`: The settings and handles of the source
+/// - `ingest`: The ingestion pipeline that the data is pushed into. It writes the data to the database in batches
+///
+async fn begin_network_table(mut source: NetworkTableSource, ingest: Ingest) {
+    println!("Starting NetworkTable Bridge");
+    if source.config.subscription_groups.is_empty() {
+        let group = SubscriptionGroup::default_for(&source.config.topic_filter);
+        source.config.subscription_groups.push(group);
+    }
+    // first, so the schemas are there before the topics that need them
+    source
+        .config
+        .subscription_groups
+        .insert(0, SubscriptionGroup::schemas());
+    let mut backoff = Backoff::new(source.config.reconnect_policy);
+    let mut last_working = None;
+    let mut first = true;
+    loop {
+        if !first && !backoff.wait(&TokioClock).await {
+            println!("Giving up on NetworkTables after too many failed attempts");
+            source.connection.gave_up();
+            break;
+        }
+        first = false;
+
+        let mut connected = false;
+        for protocol in source.config.protocol.attempts(last_working) {
+            connected = match protocol {
+                Protocol::V4 => source.record_v4(&ingest).await,
+                Protocol::V3 => source.record_v3(&ingest).await,
+            };
+            if connected {
+                last_working = Some(protocol);
+                break;
             }
+        }
 
-            let client = network_tables::v4::Client::try_new_w_config(
-                SocketAddrV4::new(Ipv4Addr::from_str(&url).unwrap(), port as u16),
-                network_tables::v4::client_config::Config {
-                    ..Default::default()
-                },
-            )
-            .await;
+        if connected {
+            backoff.reset();
+        } else {
+            println!("Failed to connect to NetworkTables");
+        }
+    }
+}
 
-            println!("Connected to NetworkTables");
+impl NetworkTableSource {
+    /// # Function
+    /// Connects with NT4 and records until the connection is lost. Every connection is its own recording session.
+    ///
+    /// # Returns
+    /// `false` if it could not connect (or subscribe)
+    ///
+    async fn record_v4(&self, ingest: &Ingest) -> bool {
+        let config = &self.config;
+        let connection = &self.connection;
+        let Some(mut client) = connect(&config.robot_address, config.port, connection, |address| {
+            Nt4Client::connect(address, IDENTITY)
+        })
+        .await
+        else {
+            return false;
+        };
 
-            if client.is_err() {
-                println!("Failed to connect to NetworkTables");
-                first = false;
-                continue;
+        if let Err(err) = subscribe(&client, &config.subscription_groups).await {
+            println!("Failed to subscribe to NetworkTables");
+            connection.failed(err);
+            return false;
+        }
+
+        println!("Connected to NetworkTables {} (NT4)", config.name);
+        connection.connected();
+        if let Some(writer) = &self.writer {
+            writer.set_publisher(Some(client.publisher())).await;
+        }
+
+        self.start_session(ingest).await;
+        let mut clock = ClockSync::default(); // the server time starts over when the robot reboots
+        while let Some(event) = client.next().await {
+            match event {
+                Nt4Event::Announce {
+                    name,
+                    value_type,
+                    id,
+                    properties,
+                } => {
+                    // only the topics that are recorded are in the catalog, like with NT3
+                    if self.owner(&name).is_some() {
+                        let mut topic =
+                            TopicInfo::from_properties(name, value_type, id, properties);
+                        topic.source = Some(config.name.clone());
+                        ingest.announce(topic).await;
+                    }
+                }
+                Nt4Event::Unannounce(name) => {
+                    ingest
+                        .unannounce(Some(name), Some(config.name.clone()))
+                        .await
+                }
+                Nt4Event::Value {
+                    name,
+                    value_type,
+                    type_id,
+                    value,
+                    timestamp,
+                } => {
+                    let received = now_micros();
+                    connection.message_received();
+                    if !self.records_values(&name) {
+                        continue;
+                    }
+
+                    // struct and protobuf topics are raw data with a type string that NT4 does not know
+                    let r#type = Type::from_num(type_id as u64).unwrap_or(Type::Raw);
+                    let mut entree =
+                        TableEntree::from_value(name, value_type, r#type, &value, timestamp);
+                    entree.wall_time = Some(clock.wall_time(timestamp, received));
+                    entree.source = Some(config.name.clone());
+
+                    (self.function_to_call)(entree, ingest);
+                }
             }
+        }
 
-            let client = client.unwrap();
-            let subscription = client
-                .subscribe_w_options(
-                    &[""],
-                    Some(SubscriptionOptions {
-                        all: Some(true),
-                        prefix: Some(true),
-                        ..Default::default()
-                    }),
-                )
-                .await;
+        connection.disconnected();
+        if let Some(writer) = &self.writer {
+            writer.set_publisher(None).await;
+        }
+        self.end_session(ingest).await;
+        true
+    }
+
+    /// # Function
+    /// Connects with NT3 and records until the connection is lost. The NT3 server sends every entry, the topic filter and the
+    /// subscription groups decide what is recorded like they would for NT4. NT3 has no server time, the timestamps are the time
+    /// since the connection was made. Writing from the dashboard only works with NT4.
+    ///
+    /// # Returns
+    /// `false` if it could not connect
+    ///
+    async fn record_v3(&self, ingest: &Ingest) -> bool {
+        let config = &self.config;
+        let connection = &self.connection;
+        let Some(mut client) = connect(
+            &config.robot_address,
+            config.v3_port,
+            connection,
+            |address| Nt3Client::connect(address, IDENTITY),
+        )
+        .await
+        else {
+            return false;
+        };
 
-            if subscription.is_err() {
-                println!("Failed to subscribe to NetworkTables");
-                first = false;
+        println!("Connected to NetworkTables {} (NT3)", config.name);
+        connection.connected();
+
+        self.start_session(ingest).await;
+        while let Some(event) = client.next().await {
+            match event {
+                Nt3Event::Announce {
+                    name,
+                    value_type,
+                    id,
+                    persistent,
+                } => {
+                    if self.owner(&name).is_some() {
+                        let mut topic = TopicInfo::from_nt3(name, value_type, id, persistent);
+                        topic.source = Some(config.name.clone());
+                        ingest.announce(topic).await;
+                    }
+                }
+                Nt3Event::Unannounce(name) => {
+                    ingest.unannounce(name, Some(config.name.clone())).await
+                }
+                Nt3Event::Value {
+                    name,
+                    value_type,
+                    value,
+                    timestamp,
+                } => {
+                    connection.message_received();
+                    if !self.records_values(&name) {
+                        continue;
+                    }
+
+                    let mut entree = TableEntree::with_type(name, value_type, value, timestamp);
+                    entree.wall_time = Some(now_micros());
+                    entree.source = Some(config.name.clone());
+                    (self.function_to_call)(entree, ingest);
+                }
+            }
+        }
+
+        connection.disconnected();
+        self.end_session(ingest).await;
+        true
+    }
+
+    /// The group that records a topic, `None` if the topic is not recorded. Overlapping groups get the same topic, only the
+    /// group with the longest matching prefix records it. Schema topics are always recorded, whatever the topic filter says
+    fn owner(&self, name: &str) -> Option<usize> {
+        if !name.starts_with(SCHEMA_PREFIX) && !self.config.topic_filter.matches(name) {
+            return None;
+        }
+
+        subscription_group::owner(&self.config.subscription_groups, name)
+    }
+
+    /// If the values of a topic are recorded (its group is not `topics_only`)
+    fn records_values(&self, name: &str) -> bool {
+        self.owner(name)
+            .is_some_and(|group| !self.config.subscription_groups[group].topics_only)
+    }
+
+    /// Every connection gets its own recording session, if this source owns the sessions. Starting a session ends the one
+    /// before it
+    async fn start_session(&self, ingest: &Ingest) {
+        if self.config.owns_sessions {
+            ingest.start_session().await;
+        }
+    }
+
+    /// The topics of this connection are not announced anymore, and its session ends if this source owns the sessions
+    async fn end_session(&self, ingest: &Ingest) {
+        ingest
+            .unannounce(None, Some(self.config.name.clone()))
+            .await;
+        if self.config.owns_sessions {
+            ingest.end_session().await;
+        }
+    }
+}
+
+/// # Function
+/// Makes one subscription for every subscription group, in the same order.
+///
+async fn subscribe(client: &Nt4Client, groups: &[SubscriptionGroup]) -> Result<(), String> {
+    for group in groups {
+        println!("Subscribing to {:?} ({})", group.prefixes, group.name);
+        client.subscribe(&group.prefixes, group.options()).await?;
+    }
+
+    Ok(())
+}
+
+/// # Function
+/// Tries every candidate address of the robot in order and connects to the first one that works.
+///
+/// # Parameters
+/// - `robot_address`: Where the network table is
+/// - `port`: The port of the network table
+/// - `connection`: Gets updated for every address that is tried
+/// - `try_connect`: Connects to one address (with NT4 or NT3)
+///
+/// # Returns
+/// `None` if no candidate worked, the last error is then in `connection`
+///
+async fn connect<T, E: ToString, F: Future<Output = Result<T, E>>>(
+    robot_address: &RobotAddress,
+    port: u16,
+    connection: &ConnectionTracker,
+    try_connect: impl Fn(SocketAddr) -> F,
+) -> Option<T> {
+    for host in robot_address.candidates() {
+        let addresses = match robot_address::resolve(&host, port).await {
+            Ok(addresses) => addresses,
+            Err(err) => {
+                connection.connecting(format!("{}:{}", host, port));
+                connection.failed(err);
                 continue;
             }
+        };
 
-            let mut subscription = subscription.unwrap();
-            while let Some(message) = subscription.next().await {
-                //println!("Received message: {:?}", message);
-                let _ = function_to_call(message, database.clone());
+        for address in addresses {
+            connection.connecting(address.to_string());
+            match try_connect(address).await {
+                Ok(client) => return Some(client),
+                Err(err) => connection.failed(err.to_string()),
             }
         }
-    })
+    }
+
+    None
 }
 
 /// # Function
 /// This function is used to write the data to the database when the message is received. This is used because a local database is needed for the data coming in from the network table.
-/// This is essentially the function that you pass inside the `begin_network_table` function. I separated it out into a function to make it easier to test and read.
+/// This is essentially the function that you pass to `NetworkTableSource::new`. I separated it out into a function to make it easier to test and read.
+/// The entree is only put in the ingestion queue, the writer task adds it to the database with the next batch.
 ///
 /// # Parameters
-/// - `message`: The message that will be written to the database
-/// - `database`: The database that will be used to store the data
+/// - `entree`: The entree that will be written to the database
+/// - `ingest`: The ingestion pipeline that will be used to store the data
+///
+pub fn write_all(entree: TableEntree, ingest: &Ingest) {
+    ingest.push(entree);
+}
+
+/// # Function
+/// Moves robot timestamps (the NT4 server time, which starts over when the robot reboots) to the wall clock of the backend.
+/// A value can not be received before it was sent, so the smallest `received - robot time` that was seen is the offset
+/// between the clocks plus the smallest latency. Old values (e.g. the ones the server sends right after subscribing) have
+/// a bigger difference and do not change the offset.
+///
+/// # Usage
+/// Make a new one for every connection (together with a new session) and pass every timestamp with the wall clock time it
+/// was received at.
 ///
-pub fn write_all(message: MessageData, database: Arc<Mutex<SQLiteDatabase>>) -> () {
-    let mut message = message;
-    message.timestamp /= 1000;
-    let _res = database
-        .lock()
-        .unwrap()
-        .add_value(TableEntree::from_message(message));
+#[derive(Debug, Default)]
+pub struct ClockSync {
+    offset: Option<i64>,
+    last_robot_time: u64,
+}
+
+impl ClockSync {
+    /// The offset can go up by this much per microsecond of robot time (100 ppm), so clocks that drift apart are followed
+    const DRIFT: f64 = 0.0001;
+
+    pub fn wall_time(&mut self, robot_time: u64, received: u64) -> u64 {
+        let sample = received as i64 - robot_time as i64;
+        let offset = match self.offset {
+            Some(offset) => {
+                let elapsed = robot_time.saturating_sub(self.last_robot_time);
+                sample.min(offset + (elapsed as f64 * Self::DRIFT) as i64)
+            }
+            None => sample,
+        };
+
+        self.offset = Some(offset);
+        self.last_robot_time = self.last_robot_time.max(robot_time);
+        (robot_time as i64 + offset).max(0) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use network_tables::Value;
+    use test_server::TestServer;
+    use writer::WriteRequest;
+
+    use super::*;
+    use crate::{
+        database::{structs::table_value::TableValue, SQLiteDatabase},
+        ingest::IngestConfig,
+        live::LiveFeed,
+        network_table_bridge::connection_status::ConnectionState,
+    };
+
+    struct Bridge {
+        database: Arc<Mutex<SQLiteDatabase>>,
+        connection: ConnectionTracker,
+        writer: NetworkTableWriter,
+    }
+
+    impl Bridge {
+        ///
+        /// # Function
+        /// Runs a `NetworkTableSource` that is connected to the test server, with the ingestion pipeline writing to `test.db`.
+        /// Has to be called inside a `LocalSet`.
+        ///
+        fn start(server: &TestServer) -> Self {
+            Self::start_with(
+                server.address(),
+                ProtocolMode::V4,
+                0,
+                TopicFilter::from_lists("", "/Ignored/*"),
+            )
+        }
+
+        ///
+        /// # Function
+        /// Like `start`, but with any NT4 address, the NT3 settings and a topic filter.
+        ///
+        fn start_with(
+            address: SocketAddr,
+            protocol: ProtocolMode,
+            v3_port: u16,
+            topic_filter: TopicFilter,
+        ) -> Self {
+            let database = SQLiteDatabase::new("test.db", 2).unwrap();
+            database.clear_database().unwrap();
+            let database = Arc::new(Mutex::new(database));
+
+            let connection = ConnectionTracker::new(
+                "robot".to_string(),
+                address.to_string(),
+                LiveFeed::default(),
+            );
+            let writer =
+                NetworkTableWriter::new(TopicFilter::from_lists("/Tuning/*", ""), database.clone());
+            let (ingest, ingest_writer) = Ingest::new(
+                IngestConfig {
+                    max_batch_delay: 10,
+                    ..Default::default()
+                },
+                LiveFeed::default(),
+            );
+            tokio::spawn(ingest_writer.run(database.clone()));
+
+            let source = Box::new(NetworkTableSource::new(
+                BridgeConfig {
+                    name: "robot".to_string(),
+                    owns_sessions: true,
+                    robot_address: RobotAddress::Host(address.ip().to_string()),
+                    port: address.port(),
+                    protocol,
+                    v3_port,
+                    reconnect_policy: ReconnectPolicy::default(),
+                    topic_filter,
+                    subscription_groups: Vec::new(),
+                },
+                Box::new(write_all),
+                connection.clone(),
+                Some(writer.clone()),
+            ));
+            tokio::task::spawn_local(source.run(ingest));
+
+            Self {
+                database,
+                connection,
+                writer,
+            }
+        }
+
+        /// The newest value of a topic in the current session
+        fn value(&self, topic: &str) -> Option<TableValue> {
+            self.values(topic).into_iter().next()
+        }
+
+        fn values(&self, topic: &str) -> Vec<TableValue> {
+            let database = self.database.lock().unwrap();
+            let values = database.get_values_no_time(topic, 100).unwrap();
+            values.into_iter().map(|entree| entree.value).collect()
+        }
+
+        fn state(&self) -> ConnectionState {
+            self.connection.status().state
+        }
+    }
+
+    /// Waits (at most 5 seconds) until the condition is true
+    async fn wait_for(condition: impl Fn() -> bool) {
+        let result = tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        assert!(result.is_ok(), "timed out");
+    }
+
+    #[test]
+    fn test_clock_sync() {
+        let mut clock = ClockSync::default();
+        // 2 ms of latency
+        assert_eq!(clock.wall_time(1_000_000, 5_002_000), 5_002_000);
+        // less latency, the offset gets smaller
+        assert_eq!(clock.wall_time(2_000_000, 6_000_500), 6_000_500);
+        // an old value (sent right after subscribing) keeps its own time
+        assert_eq!(clock.wall_time(500_000, 6_100_000), 4_500_500);
+        // more latency does not change the offset much
+        assert_eq!(clock.wall_time(3_000_000, 7_050_000), 7_000_600);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_records_published_values() {
+        let server = TestServer::start().await;
+        server
+            .publish("/SmartDashboard/Speed", "double", Value::F64(1.5))
+            .await;
+        server
+            .publish("/Ignored/Value", "int", Value::from(1))
+            .await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start(&server);
+
+                // the value that was there before the bridge connected
+                wait_for(|| bridge.value("/SmartDashboard/Speed").is_some()).await;
+                assert_eq!(bridge.state(), ConnectionState::Connected);
+                assert_eq!(
+                    bridge.value("/SmartDashboard/Speed"),
+                    Some(TableValue::Double(1.5))
+                );
+
+                server
+                    .publish("/SmartDashboard/Speed", "double", Value::F64(2.5))
+                    .await;
+                server
+                    .publish("/SmartDashboard/Enabled", "boolean", Value::from(true))
+                    .await;
+                wait_for(|| bridge.value("/SmartDashboard/Enabled").is_some()).await;
+                wait_for(|| bridge.value("/SmartDashboard/Speed") == Some(TableValue::Double(2.5)))
+                    .await;
+
+                server.unannounce("/SmartDashboard/Enabled").await;
+                wait_for(|| {
+                    let database = bridge.database.lock().unwrap();
+                    let topics = database.get_topics("/SmartDashboard/Enabled").unwrap();
+                    topics.iter().all(|topic| !topic.announced)
+                })
+                .await;
+
+                let database = bridge.database.lock().unwrap();
+                assert_eq!(database.topic_length("/SmartDashboard/Speed").unwrap(), 2);
+                assert_eq!(database.topic_length("/Ignored/Value").unwrap(), 0);
+                let topics = database.get_topics("").unwrap();
+                assert!(topics
+                    .iter()
+                    .any(|topic| topic.name == "/SmartDashboard/Enabled"));
+            })
+            .await;
+        server.stop();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_catalogs_only_recorded_topics() {
+        let server = TestServer::start().await;
+        server
+            .publish("/Ignored/Value", "int", Value::from(1))
+            .await;
+        server
+            .publish("/SmartDashboard/Speed", "double", Value::F64(1.5))
+            .await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start(&server);
+                wait_for(|| bridge.value("/SmartDashboard/Speed").is_some()).await;
+                // everything that was sent before this value is written by now
+                server
+                    .publish("/SmartDashboard/Speed", "double", Value::F64(2.5))
+                    .await;
+                wait_for(|| bridge.value("/SmartDashboard/Speed") == Some(TableValue::Double(2.5)))
+                    .await;
+
+                // the server announces both, the denied one is not in the catalog
+                let topics = bridge.database.lock().unwrap().get_topics("").unwrap();
+                assert!(topics
+                    .iter()
+                    .any(|topic| topic.name == "/SmartDashboard/Speed"));
+                assert!(!topics.iter().any(|topic| topic.name == "/Ignored/Value"));
+            })
+            .await;
+        server.stop();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_decodes_struct_topics() {
+        let server = TestServer::start().await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                // the schemas get through also if the filter does not allow them
+                let bridge = Bridge::start_with(
+                    server.address(),
+                    ProtocolMode::V4,
+                    0,
+                    TopicFilter::from_lists("/SmartDashboard/*", "/.schema/*"),
+                );
+                wait_for(|| bridge.state() == ConnectionState::Connected).await;
+
+                // the way WPILib publishes a struct topic, with a type string that is not an NT4 type
+                server
+                    .publish(
+                        "/.schema/struct:Translation2d",
+                        "structschema",
+                        Value::Binary(b"double x;double y".to_vec()),
+                    )
+                    .await;
+                wait_for(|| bridge.value("/.schema/struct:Translation2d").is_some()).await;
+                let bytes = [1.5f64.to_le_bytes(), (-2.0f64).to_le_bytes()].concat();
+                server
+                    .publish(
+                        "/SmartDashboard/Position",
+                        "struct:Translation2d",
+                        Value::Binary(bytes.clone()),
+                    )
+                    .await;
+                wait_for(|| bridge.value("/SmartDashboard/Position").is_some()).await;
+
+                let database = bridge.database.lock().unwrap();
+                let entree = database.get_value("/SmartDashboard/Position").unwrap();
+                assert_eq!(entree.value_type, "struct:Translation2d");
+                assert_eq!(entree.value, TableValue::Raw(bytes));
+                assert_eq!(
+                    entree.decoded,
+                    Some(serde_json::json!({"x": 1.5, "y": -2.0}))
+                );
+                let topics = database.get_topics("/SmartDashboard/Position").unwrap();
+                assert_eq!(topics[0].value_type, "struct:Translation2d");
+            })
+            .await;
+        server.stop();
+    }
+
+    /// A protobuf field with a length (every length in the tests is below 128)
+    fn bytes_field(number: u8, data: &[u8]) -> Vec<u8> {
+        [vec![(number << 3) | 2, data.len() as u8], data.to_vec()].concat()
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_decodes_protobuf_topics() {
+        let server = TestServer::start().await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start(&server);
+                wait_for(|| bridge.state() == ConnectionState::Connected).await;
+
+                // `package wpi.proto; message ProtobufTranslation2d {double x = 1; double y = 2;}`
+                let field = |name: &[u8], number: u8| {
+                    let mut field = bytes_field(1, name);
+                    field.extend([3 << 3, number, 4 << 3, 1, 5 << 3, 1]); // number, optional, double
+                    bytes_field(2, &field)
+                };
+                let message = [
+                    bytes_field(1, b"ProtobufTranslation2d"),
+                    field(b"x", 1),
+                    field(b"y", 2),
+                ]
+                .concat();
+                let file = [
+                    bytes_field(1, b"geometry2d.proto"),
+                    bytes_field(2, b"wpi.proto"),
+                    bytes_field(4, &message),
+                ]
+                .concat();
+                server
+                    .publish(
+                        "/.schema/proto:geometry2d.proto",
+                        "proto:FileDescriptorProto",
+                        Value::Binary(file),
+                    )
+                    .await;
+                wait_for(|| bridge.value("/.schema/proto:geometry2d.proto").is_some()).await;
+
+                let mut bytes = vec![(1 << 3) | 1];
+                bytes.extend(1.5f64.to_le_bytes());
+                bytes.push((2 << 3) | 1);
+                bytes.extend((-2.0f64).to_le_bytes());
+                server
+                    .publish(
+                        "/SmartDashboard/Position",
+                        "proto:wpi.proto.ProtobufTranslation2d",
+                        Value::Binary(bytes.clone()),
+                    )
+                    .await;
+                wait_for(|| bridge.value("/SmartDashboard/Position").is_some()).await;
+
+                let database = bridge.database.lock().unwrap();
+                let entree = database.get_value("/SmartDashboard/Position").unwrap();
+                assert_eq!(entree.value_type, "proto:wpi.proto.ProtobufTranslation2d");
+                assert_eq!(entree.value, TableValue::Raw(bytes));
+                assert_eq!(
+                    entree.decoded,
+                    Some(serde_json::json!({"x": 1.5, "y": -2.0}))
+                );
+            })
+            .await;
+        server.stop();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_writes_reach_the_server() {
+        let server = TestServer::start().await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start(&server);
+                wait_for(|| bridge.state() == ConnectionState::Connected).await;
+
+                let request = WriteRequest {
+                    topic: "/Tuning/P".to_string(),
+                    value_type: "double".to_string(),
+                    value: serde_json::json!(0.05),
+                };
+                // the writer gets the client right after the subscription is made
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                bridge.writer.write(request, "http", None).await.unwrap();
+
+                wait_for(|| server.value("/Tuning/P") == Some(Value::F64(0.05))).await;
+            })
+            .await;
+        server.stop();
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_reconnects_after_server_restart() {
+        let server = TestServer::start().await;
+        let address = server.address();
+        server
+            .publish("/Drive/Speed", "double", Value::F64(1.0))
+            .await;
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start(&server);
+                wait_for(|| bridge.value("/Drive/Speed").is_some()).await;
+                let first_session = bridge.database.lock().unwrap().current_session();
+
+                // the robot is turned off
+                server.stop();
+                wait_for(|| bridge.state() == ConnectionState::Disconnected).await;
+                assert!(bridge
+                    .database
+                    .lock()
+                    .unwrap()
+                    .get_topics("")
+                    .unwrap()
+                    .iter()
+                    .all(|topic| !topic.announced));
+
+                // and turned on again, its clock starts over
+                let server = TestServer::start_on(address).await;
+                server
+                    .publish("/Drive/Speed", "double", Value::F64(3.0))
+                    .await;
+                wait_for(|| bridge.value("/Drive/Speed") == Some(TableValue::Double(3.0))).await;
+                assert_eq!(bridge.state(), ConnectionState::Connected);
+
+                // the values from before the reboot are in their own session
+                let database = bridge.database.lock().unwrap();
+                assert_ne!(database.current_session(), first_session);
+                assert_eq!(
+                    database.get_value("/Drive/Speed").unwrap().value,
+                    TableValue::Double(3.0)
+                );
+                assert_eq!(
+                    database
+                        .get_session_value(first_session, "/Drive/Speed", None)
+                        .unwrap()
+                        .value,
+                    TableValue::Double(1.0)
+                );
+                // and the new clock is not mixed with the old one: the new session only has the values of the new server,
+                // and their wall clock time comes after the old values
+                let old = database
+                    .get_session_values(first_session, "/Drive/Speed", None, u64::MAX, 100)
+                    .unwrap();
+                let new = database
+                    .get_session_values(
+                        database.current_session(),
+                        "/Drive/Speed",
+                        None,
+                        u64::MAX,
+                        100,
+                    )
+                    .unwrap();
+                assert!(new
+                    .iter()
+                    .all(|entree| entree.value == TableValue::Double(3.0)));
+                assert!(new[0].wall_time >= old[0].wall_time);
+                let sessions = database.get_sessions().unwrap();
+                assert!(sessions.iter().any(|session| session.id == first_session));
+                assert!(sessions.len() >= 2);
+                drop(database);
+                server.stop();
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_falls_back_to_nt3() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // nothing is listening on the NT4 port
+        let nt4_address = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let v3_port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut hello = vec![0; 4 + IDENTITY.len()];
+            stream.read_exact(&mut hello).await.unwrap();
+
+            // server hello, two entries (a double and an ignored boolean) and server hello complete
+            let mut bytes = vec![0x04, 0x00, 0x00];
+            bytes.extend([0x10, 21]);
+            bytes.extend(b"/SmartDashboard/Speed");
+            bytes.extend([0x01, 0, 1, 0, 1, 0]);
+            bytes.extend(2.5f64.to_be_bytes());
+            bytes.extend([0x10, 14]);
+            bytes.extend(b"/Ignored/Value");
+            bytes.extend([0x00, 0, 2, 0, 1, 0, 1]);
+            bytes.push(0x03);
+            stream.write_all(&bytes).await.unwrap();
+
+            let mut complete = [0];
+            stream.read_exact(&mut complete).await.unwrap();
+            let mut update = vec![0x11, 0, 1, 0, 2, 0x01];
+            update.extend(3.5f64.to_be_bytes());
+            stream.write_all(&update).await.unwrap();
+            stream
+        });
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let bridge = Bridge::start_with(
+                    nt4_address,
+                    ProtocolMode::Auto,
+                    v3_port,
+                    TopicFilter::from_lists("", "/Ignored/*"),
+                );
+
+                wait_for(|| bridge.values("/SmartDashboard/Speed").len() == 2).await;
+                assert_eq!(
+                    bridge.values("/SmartDashboard/Speed"),
+                    vec![TableValue::Double(3.5), TableValue::Double(2.5)]
+                );
+                assert_eq!(bridge.state(), ConnectionState::Connected);
+                assert!(bridge.values("/Ignored/Value").is_empty());
+
+                let stream = server.await.unwrap();
+                drop(stream);
+                wait_for(|| bridge.state() != ConnectionState::Connected).await;
+            })
+            .await;
+    }
 }
@@ -0,0 +1,111 @@
+use super::{
+    protocol_mode::ProtocolMode, reconnect_policy::ReconnectPolicy, robot_address::RobotAddress,
+    subscription_group::SubscriptionGroup, topic_filter::TopicFilter, BridgeConfig,
+};
+
+///
+/// # Function
+/// A network table connection next to the main one (e.g. a simulator or a vision coprocessor that hosts its own NT server).
+/// Every connection has its own address, filters and reconnect state. Entries are tagged with the `name`, the recording
+/// sessions are only started and ended by the main connection.
+///
+/// # Fields
+/// - `name`: The name of the connection, it has to be unique. Stored as the `source` of every entry
+/// - `address`: Where the server is (team number, hostname or IP, like `NETWORK_TABLE_IP`)
+/// - `port`: The NT4 port, `5810` by default
+/// - `protocol`: `v4`, `v3` or `auto` (default)
+/// - `v3_port`: The NT3 port, `1735` by default
+/// - `allowlist`, `denylist`: Topic patterns like `NETWORK_TABLE_TOPIC_ALLOWLIST` and `NETWORK_TABLE_TOPIC_DENYLIST`
+/// - `subscription_groups`: Like `NETWORK_TABLE_SUBSCRIPTION_GROUPS`
+///
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct ConnectionConfig {
+    pub name: String,
+    pub address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: ProtocolMode,
+    #[serde(default = "default_v3_port")]
+    pub v3_port: u16,
+    #[serde(default)]
+    pub allowlist: String,
+    #[serde(default)]
+    pub denylist: String,
+    #[serde(default)]
+    pub subscription_groups: Vec<SubscriptionGroup>,
+}
+
+fn default_port() -> u16 {
+    5810
+}
+
+fn default_v3_port() -> u16 {
+    1735
+}
+
+impl ConnectionConfig {
+    ///
+    /// # Function
+    /// Reads the connections from JSON (the format used in the .env file), e.g.
+    /// `[{"name": "sim", "address": "localhost"}, {"name": "vision", "address": "10.0.0.11", "allowlist": "/photonvision/"}]`
+    ///
+    pub fn parse_list(json: &str) -> Result<Vec<Self>, serde_json::Error> {
+        if json.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        serde_json::from_str(json)
+    }
+
+    ///
+    /// # Function
+    /// Makes the settings of the bridge for this connection. It does not own the recording sessions.
+    ///
+    pub fn bridge_config(&self, reconnect_policy: ReconnectPolicy) -> BridgeConfig {
+        BridgeConfig {
+            name: self.name.clone(),
+            owns_sessions: false,
+            robot_address: RobotAddress::parse(&self.address),
+            port: self.port,
+            protocol: self.protocol,
+            v3_port: self.v3_port,
+            reconnect_policy,
+            topic_filter: TopicFilter::from_lists(&self.allowlist, &self.denylist),
+            subscription_groups: self.subscription_groups.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        let connections = ConnectionConfig::parse_list(
+            r#"[{"name": "sim", "address": "localhost"}, {"name": "vision", "address": "10.0.0.11", "port": 5811, "protocol": "v3", "allowlist": "/photonvision/"}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].port, 5810);
+        assert_eq!(connections[0].protocol, ProtocolMode::Auto);
+        assert_eq!(connections[0].v3_port, 1735);
+        assert_eq!(connections[1].protocol, ProtocolMode::V3);
+
+        let config = connections[1].bridge_config(ReconnectPolicy::default());
+        assert_eq!(config.name, "vision");
+        assert!(!config.owns_sessions);
+        assert_eq!(config.port, 5811);
+        assert!(config.topic_filter.matches("/photonvision/camera/latency"));
+        assert!(!config.topic_filter.matches("/SmartDashboard/Speed"));
+
+        assert!(ConnectionConfig::parse_list("").unwrap().is_empty());
+        assert!(ConnectionConfig::parse_list(r#"[{"name": "sim"}]"#).is_err());
+        assert!(ConnectionConfig::parse_list(
+            r#"[{"name": "sim", "address": "localhost", "protocol": "v2"}]"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{database::now_micros, live::LiveFeed};
+
+///
+/// # Function
+/// The state of the link between the backend and the network table server.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    #[default]
+    Disconnected,
+    /// The bridge gave up after too many failed attempts (see `ReconnectPolicy::max_attempts`), it does not connect again
+    Failed,
+}
+
+///
+/// # Function
+/// A snapshot of the network table connection. This is what `/status/network-table` and the live WebSocket send to the dashboard.
+///
+/// # Fields
+/// - `source`: The name of the network table connection
+/// - `state`: If the backend is connecting, connected, disconnected or gave up
+/// - `address`: The address of the network table server (`ip:port`)
+/// - `connected_since`: When the current connection was made (UNIX microseconds), `null` if not connected
+/// - `reconnect_attempts`: The amount of connection attempts since the link was last up
+/// - `last_error`: The last error that happened while connecting, `null` if there was none
+/// - `messages_received`: The amount of messages received from the network table since the backend started
+///
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct ConnectionStatus {
+    #[serde(default)]
+    pub source: String,
+    pub state: ConnectionState,
+    pub address: String,
+    pub connected_since: Option<u64>,
+    pub reconnect_attempts: u64,
+    pub last_error: Option<String>,
+    pub messages_received: u64,
+}
+
+///
+/// # Function
+/// Keeps the `ConnectionStatus` up to date. It is updated by the bridge and read by the server. Every state change is also
+/// sent to the live WebSocket clients. It is cheap to clone.
+///
+#[derive(Debug, Clone)]
+pub struct ConnectionTracker {
+    status: Arc<Mutex<ConnectionStatus>>,
+    messages_received: Arc<AtomicU64>,
+    live: LiveFeed,
+}
+
+impl ConnectionTracker {
+    ///
+    /// # Function
+    /// Makes a new tracker in the `Disconnected` state.
+    ///
+    /// # Parameters
+    /// - `source`: The name of the network table connection
+    /// - `address`: The address of the network table server
+    /// - `live`: The feed that state changes are sent to
+    ///
+    pub fn new(source: String, address: String, live: LiveFeed) -> Self {
+        Self {
+            status: Arc::new(Mutex::new(ConnectionStatus {
+                source,
+                address,
+                ..Default::default()
+            })),
+            messages_received: Arc::new(AtomicU64::new(0)),
+            live,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Gets the current status.
+    ///
+    pub fn status(&self) -> ConnectionStatus {
+        let mut status = self
+            .status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default();
+        status.messages_received = self.messages_received.load(Ordering::Relaxed);
+        status
+    }
+
+    ///
+    /// # Function
+    /// Called before every connection attempt.
+    ///
+    pub fn connecting(&self, address: String) {
+        self.update(|status| {
+            status.state = ConnectionState::Connecting;
+            status.address = address;
+            status.connected_since = None;
+            status.reconnect_attempts += 1;
+        });
+    }
+
+    ///
+    /// # Function
+    /// Called when the connection is made.
+    ///
+    pub fn connected(&self) {
+        self.update(|status| {
+            status.state = ConnectionState::Connected;
+            status.connected_since = Some(now_micros());
+            status.reconnect_attempts = 0;
+        });
+    }
+
+    ///
+    /// # Function
+    /// Called when a connection attempt failed.
+    ///
+    pub fn failed(&self, error: String) {
+        self.update(|status| {
+            status.state = ConnectionState::Disconnected;
+            status.connected_since = None;
+            status.last_error = Some(error);
+        });
+    }
+
+    ///
+    /// # Function
+    /// Called when the bridge stops trying to connect.
+    ///
+    pub fn gave_up(&self) {
+        self.update(|status| {
+            status.state = ConnectionState::Failed;
+            status.connected_since = None;
+        });
+    }
+
+    ///
+    /// # Function
+    /// Called when a working connection was lost.
+    ///
+    pub fn disconnected(&self) {
+        self.update(|status| {
+            status.state = ConnectionState::Disconnected;
+            status.connected_since = None;
+        });
+    }
+
+    ///
+    /// # Function
+    /// Called for every message that is received. This is not sent to the live clients on its own.
+    ///
+    pub fn message_received(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ConnectionStatus)) {
+        if let Ok(mut status) = self.status.lock() {
+            change(&mut status);
+        }
+
+        self.live.publish_status(self.status());
+    }
+}
+
+///
+/// # Function
+/// Every network table connection, the first one is the main connection (the one that is also on `/status/network-table`).
+///
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionTrackers(pub Vec<ConnectionTracker>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_changes() {
+        let tracker = ConnectionTracker::new(
+            "robot".to_string(),
+            "10.0.0.2:5810".to_string(),
+            LiveFeed::default(),
+        );
+        assert_eq!(tracker.status().state, ConnectionState::Disconnected);
+
+        tracker.connecting("10.0.0.2:5810".to_string());
+        tracker.failed("Connection refused".to_string());
+        tracker.connecting("10.0.0.2:5810".to_string());
+
+        let status = tracker.status();
+        assert_eq!(status.state, ConnectionState::Connecting);
+        assert_eq!(status.reconnect_attempts, 2);
+        assert_eq!(status.last_error, Some("Connection refused".to_string()));
+
+        tracker.connected();
+        tracker.message_received();
+        tracker.message_received();
+
+        let status = tracker.status();
+        assert_eq!(status.state, ConnectionState::Connected);
+        assert_eq!(status.reconnect_attempts, 0);
+        assert!(status.connected_since.is_some());
+        assert_eq!(status.messages_received, 2);
+
+        tracker.disconnected();
+        assert_eq!(tracker.status().connected_since, None);
+
+        tracker.gave_up();
+        assert_eq!(tracker.status().state, ConnectionState::Failed);
+    }
+}
@@ -0,0 +1,658 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{interval, timeout, Instant, Interval, MissedTickBehavior},
+};
+
+use crate::database::structs::table_value::TableValue;
+
+/// The protocol revision that is sent in the client hello (3.0)
+const PROTOCOL_REVISION: u16 = 0x0300;
+/// The magic number of the "clear all entries" message
+const CLEAR_ALL_MAGIC: u32 = 0xD06C_B27A;
+/// The server drops clients that are quiet for too long, a keep alive is sent when nothing else was
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the server gets to send its hello and the initial entries
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+///
+/// # Function
+/// A message of the NT3 protocol (TCP, big endian, LEB128 lengths). Only the messages that a client receives or sends are here.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nt3Message {
+    KeepAlive,
+    ClientHello {
+        identity: String,
+    },
+    ProtocolUnsupported {
+        revision: u16,
+    },
+    ServerHelloComplete,
+    ServerHello {
+        identity: String,
+    },
+    ClientHelloComplete,
+    EntryAssignment {
+        name: String,
+        id: u16,
+        flags: u8,
+        value: TableValue,
+        value_type: String,
+    },
+    EntryUpdate {
+        id: u16,
+        value: TableValue,
+        value_type: String,
+    },
+    EntryFlagsUpdate {
+        id: u16,
+        flags: u8,
+    },
+    EntryDelete {
+        id: u16,
+    },
+    ClearAllEntries,
+    /// RPC calls and responses are read so the stream stays in sync, but they are not used
+    Rpc,
+}
+
+impl Nt3Message {
+    ///
+    /// # Function
+    /// Reads one message from the start of `bytes`.
+    ///
+    /// # Returns
+    /// - `Ok(Some((message, length)))` if a whole message is there, `length` is the amount of bytes it used
+    /// - `Ok(None)` if more bytes are needed
+    /// - `Err` if the data is not valid NT3, the connection can not be used after that
+    ///
+    pub fn decode(bytes: &[u8]) -> Result<Option<(Self, usize)>, String> {
+        let mut reader = Reader { bytes, position: 0 };
+        match reader.message() {
+            Ok(message) => Ok(Some((message, reader.position))),
+            Err(ReadError::Incomplete) => Ok(None),
+            Err(ReadError::Invalid(err)) => Err(err),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Writes the messages that a client sends. The others are written as nothing.
+    ///
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Nt3Message::KeepAlive => vec![0x00],
+            Nt3Message::ClientHello { identity } => {
+                let mut bytes = vec![0x01];
+                bytes.extend(PROTOCOL_REVISION.to_be_bytes());
+                write_string(&mut bytes, identity);
+                bytes
+            }
+            Nt3Message::ClientHelloComplete => vec![0x05],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ReadError {
+    Incomplete,
+    Invalid(String),
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Result<&[u8], ReadError> {
+        let data = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or(ReadError::Incomplete)?;
+        self.position += length;
+        Ok(data)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReadError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReadError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, ReadError> {
+        Ok(f64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn leb128(&mut self) -> Result<usize, ReadError> {
+        let mut value = 0usize;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(ReadError::Invalid("LEB128 length is too long".to_string()))
+    }
+
+    fn raw(&mut self) -> Result<Vec<u8>, ReadError> {
+        let length = self.leb128()?;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, ReadError> {
+        String::from_utf8(self.raw()?)
+            .map_err(|_| ReadError::Invalid("a string is not valid UTF-8".to_string()))
+    }
+
+    /// Reads a value of an NT3 type and gives back the NT4 type string that is used for it
+    fn value(&mut self, value_type: u8) -> Result<(TableValue, String), ReadError> {
+        let value = match value_type {
+            0x00 => TableValue::Boolean(self.u8()? != 0),
+            0x01 => TableValue::Double(self.f64()?),
+            0x02 => TableValue::String(self.string()?),
+            0x03 => TableValue::Raw(self.raw()?),
+            0x10 => {
+                let count = self.u8()? as usize;
+                TableValue::BooleanArray(self.take(count)?.iter().map(|&b| b != 0).collect())
+            }
+            0x11 => {
+                let count = self.u8()? as usize;
+                TableValue::DoubleArray((0..count).map(|_| self.f64()).collect::<Result<_, _>>()?)
+            }
+            0x12 => {
+                let count = self.u8()? as usize;
+                TableValue::StringArray(
+                    (0..count)
+                        .map(|_| self.string())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            0x20 => return Ok((TableValue::Raw(self.raw()?), "rpc".to_string())),
+            _ => {
+                return Err(ReadError::Invalid(format!(
+                    "unknown entry type 0x{:02x}",
+                    value_type
+                )))
+            }
+        };
+
+        let value_type = value.type_name().to_string();
+        Ok((value, value_type))
+    }
+
+    fn message(&mut self) -> Result<Nt3Message, ReadError> {
+        Ok(match self.u8()? {
+            0x00 => Nt3Message::KeepAlive,
+            0x01 => {
+                self.u16()?;
+                Nt3Message::ClientHello {
+                    identity: self.string()?,
+                }
+            }
+            0x02 => Nt3Message::ProtocolUnsupported {
+                revision: self.u16()?,
+            },
+            0x03 => Nt3Message::ServerHelloComplete,
+            0x04 => {
+                self.u8()?; // flags
+                Nt3Message::ServerHello {
+                    identity: self.string()?,
+                }
+            }
+            0x05 => Nt3Message::ClientHelloComplete,
+            0x10 => {
+                let name = self.string()?;
+                let entry_type = self.u8()?;
+                let id = self.u16()?;
+                self.u16()?; // sequence number
+                let flags = self.u8()?;
+                let (value, value_type) = self.value(entry_type)?;
+                Nt3Message::EntryAssignment {
+                    name,
+                    id,
+                    flags,
+                    value,
+                    value_type,
+                }
+            }
+            0x11 => {
+                let id = self.u16()?;
+                self.u16()?; // sequence number
+                let entry_type = self.u8()?;
+                let (value, value_type) = self.value(entry_type)?;
+                Nt3Message::EntryUpdate {
+                    id,
+                    value,
+                    value_type,
+                }
+            }
+            0x12 => Nt3Message::EntryFlagsUpdate {
+                id: self.u16()?,
+                flags: self.u8()?,
+            },
+            0x13 => Nt3Message::EntryDelete { id: self.u16()? },
+            0x14 => match self.u32()? {
+                CLEAR_ALL_MAGIC => Nt3Message::ClearAllEntries,
+                _ => Nt3Message::KeepAlive, // a clear without the magic number is ignored
+            },
+            0x20 | 0x21 => {
+                self.u16()?; // rpc id
+                self.u16()?; // call id
+                self.raw()?;
+                Nt3Message::Rpc
+            }
+            message_type => {
+                return Err(ReadError::Invalid(format!(
+                    "unknown message type 0x{:02x}",
+                    message_type
+                )))
+            }
+        })
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    let mut length = string.len();
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+    bytes.extend(string.as_bytes());
+}
+
+///
+/// # Function
+/// What happened to the entries of the NT3 server, in the shape of the NT4 announcements and values.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nt3Event {
+    /// A new entry (or one that got new flags). `persistent` is the persistent flag of the entry
+    Announce {
+        name: String,
+        value_type: String,
+        id: u16,
+        persistent: bool,
+    },
+    /// A new value of an entry. `timestamp` is the time since the connection was made (microseconds), NT3 has no server time
+    Value {
+        name: String,
+        value_type: String,
+        value: TableValue,
+        timestamp: u64,
+    },
+    /// An entry was deleted, `None` if every entry was deleted
+    Unannounce(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    value_type: String,
+    flags: u8,
+}
+
+///
+/// # Function
+/// A read only NT3 client. NT3 has no subscriptions, the server sends every entry and every change.
+///
+/// # Usage
+/// `Nt3Client::connect(address, identity)` does the handshake, then `next` gives the events until the connection is lost.
+///
+#[derive(Debug)]
+pub struct Nt3Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    entries: HashMap<u16, Entry>,
+    events: VecDeque<Nt3Event>,
+    start: Instant,
+    keep_alive: Interval,
+}
+
+impl Nt3Client {
+    ///
+    /// # Function
+    /// Connects to an NT3 server and waits for the initial entries.
+    ///
+    /// # Parameters
+    /// - `address`: The address of the server (port 1735 on a robot)
+    /// - `identity`: The name that the server shows for this client
+    ///
+    pub async fn connect(address: SocketAddr, identity: &str) -> Result<Self, String> {
+        let stream = timeout(HANDSHAKE_TIMEOUT, TcpStream::connect(address))
+            .await
+            .map_err(|_| "Timed out while connecting".to_string())?
+            .map_err(|err| err.to_string())?;
+        let mut keep_alive = interval(KEEP_ALIVE_INTERVAL);
+        keep_alive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+            entries: HashMap::new(),
+            events: VecDeque::new(),
+            start: Instant::now(),
+            keep_alive,
+        };
+
+        client
+            .send(Nt3Message::ClientHello {
+                identity: identity.to_string(),
+            })
+            .await?;
+        timeout(HANDSHAKE_TIMEOUT, client.handshake())
+            .await
+            .map_err(|_| "Timed out while waiting for the NT3 server hello".to_string())??;
+        client.send(Nt3Message::ClientHelloComplete).await?;
+
+        Ok(client)
+    }
+
+    async fn handshake(&mut self) -> Result<(), String> {
+        loop {
+            match read_message(&mut self.stream, &mut self.buffer).await? {
+                Nt3Message::ServerHelloComplete => return Ok(()),
+                Nt3Message::ProtocolUnsupported { revision } => {
+                    return Err(format!(
+                        "The server does not support NT3, it wants revision 0x{:04x}",
+                        revision
+                    ))
+                }
+                message => self.handle(message),
+            }
+        }
+    }
+
+    ///
+    /// # Function
+    /// Waits for the next event. Keep alives are sent while waiting.
+    ///
+    /// # Returns
+    /// `None` once the connection is lost (or the server sent something that is not NT3)
+    ///
+    pub async fn next(&mut self) -> Option<Nt3Event> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            let message = tokio::select! {
+                message = read_message(&mut self.stream, &mut self.buffer) => Some(message.ok()?),
+                _ = self.keep_alive.tick() => None,
+            };
+            match message {
+                Some(message) => self.handle(message),
+                None => self.send(Nt3Message::KeepAlive).await.ok()?,
+            }
+        }
+    }
+
+    async fn send(&mut self, message: Nt3Message) -> Result<(), String> {
+        self.stream
+            .write_all(&message.encode())
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    fn handle(&mut self, message: Nt3Message) {
+        let timestamp = self.start.elapsed().as_micros() as u64;
+        match message {
+            Nt3Message::EntryAssignment {
+                name,
+                id,
+                flags,
+                value,
+                value_type,
+            } => {
+                // an assignment to an id that is in use replaces the entry
+                if let Some(old) = self.entries.get(&id).filter(|old| old.name != name) {
+                    self.events
+                        .push_back(Nt3Event::Unannounce(Some(old.name.clone())));
+                }
+                self.entries.insert(
+                    id,
+                    Entry {
+                        name: name.clone(),
+                        value_type: value_type.clone(),
+                        flags,
+                    },
+                );
+                self.announce(id);
+                self.events.push_back(Nt3Event::Value {
+                    name,
+                    value_type,
+                    value,
+                    timestamp,
+                });
+            }
+            Nt3Message::EntryUpdate {
+                id,
+                value,
+                value_type,
+            } => {
+                let Some(entry) = self.entries.get_mut(&id) else {
+                    return;
+                };
+                if entry.value_type != value_type {
+                    entry.value_type = value_type.clone();
+                    self.announce(id);
+                }
+                let name = self.entries[&id].name.clone();
+                self.events.push_back(Nt3Event::Value {
+                    name,
+                    value_type,
+                    value,
+                    timestamp,
+                });
+            }
+            Nt3Message::EntryFlagsUpdate { id, flags } => {
+                if let Some(entry) = self.entries.get_mut(&id) {
+                    entry.flags = flags;
+                    self.announce(id);
+                }
+            }
+            Nt3Message::EntryDelete { id } => {
+                if let Some(entry) = self.entries.remove(&id) {
+                    self.events
+                        .push_back(Nt3Event::Unannounce(Some(entry.name)));
+                }
+            }
+            Nt3Message::ClearAllEntries => {
+                self.entries.clear();
+                self.events.push_back(Nt3Event::Unannounce(None));
+            }
+            _ => {}
+        }
+    }
+
+    fn announce(&mut self, id: u16) {
+        let entry = &self.entries[&id];
+        self.events.push_back(Nt3Event::Announce {
+            name: entry.name.clone(),
+            value_type: entry.value_type.clone(),
+            id,
+            persistent: entry.flags & 0x01 != 0,
+        });
+    }
+}
+
+/// Cancel safe, a message that was only partly read stays in the buffer
+async fn read_message(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> Result<Nt3Message, String> {
+    loop {
+        if let Some((message, length)) = Nt3Message::decode(buffer)? {
+            buffer.drain(..length);
+            return Ok(message);
+        }
+
+        if stream
+            .read_buf(buffer)
+            .await
+            .map_err(|err| err.to_string())?
+            == 0
+        {
+            return Err("The NT3 server closed the connection".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn assignment(name: &str, id: u16, flags: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x10];
+        write_string(&mut bytes, name);
+        bytes.extend(value[..1].iter());
+        bytes.extend(id.to_be_bytes());
+        bytes.extend([0, 1, flags]);
+        bytes.extend(&value[1..]);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_values() {
+        let double = [[0x01].as_slice(), &1.5f64.to_be_bytes()].concat();
+        let bytes = assignment("/SmartDashboard/Speed", 3, 1, &double);
+        assert_eq!(
+            Nt3Message::decode(&bytes).unwrap(),
+            Some((
+                Nt3Message::EntryAssignment {
+                    name: "/SmartDashboard/Speed".to_string(),
+                    id: 3,
+                    flags: 1,
+                    value: TableValue::Double(1.5),
+                    value_type: "double".to_string(),
+                },
+                bytes.len()
+            ))
+        );
+
+        // an update of a string array
+        let bytes = [0x11, 0, 3, 0, 2, 0x12, 2, 1, b'a', 2, b'b', b'c'];
+        assert_eq!(
+            Nt3Message::decode(&bytes).unwrap().unwrap().0,
+            Nt3Message::EntryUpdate {
+                id: 3,
+                value: TableValue::StringArray(vec!["a".to_string(), "bc".to_string()]),
+                value_type: "string[]".to_string(),
+            }
+        );
+
+        let bytes = [0x11, 0, 4, 0, 2, 0x10, 3, 1, 0, 1];
+        assert_eq!(
+            Nt3Message::decode(&bytes).unwrap().unwrap().0,
+            Nt3Message::EntryUpdate {
+                id: 4,
+                value: TableValue::BooleanArray(vec![true, false, true]),
+                value_type: "boolean[]".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_incomplete_and_invalid() {
+        let double = [[0x01].as_slice(), &1.5f64.to_be_bytes()].concat();
+        let bytes = assignment("/Speed", 3, 0, &double);
+        for length in 0..bytes.len() {
+            assert_eq!(Nt3Message::decode(&bytes[..length]).unwrap(), None);
+        }
+
+        assert!(Nt3Message::decode(&[0x42]).is_err());
+        assert!(Nt3Message::decode(&[0x11, 0, 3, 0, 2, 0x07]).is_err());
+        assert_eq!(
+            Nt3Message::decode(&[0x14, 0xD0, 0x6C, 0xB2, 0x7A, 0x00])
+                .unwrap()
+                .unwrap(),
+            (Nt3Message::ClearAllEntries, 5)
+        );
+    }
+
+    #[test]
+    fn test_encode_client_hello() {
+        assert_eq!(
+            Nt3Message::ClientHello {
+                identity: "dash".to_string()
+            }
+            .encode(),
+            vec![0x01, 0x03, 0x00, 4, b'd', b'a', b's', b'h']
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut hello = [0u8; 8];
+            stream.read_exact(&mut hello).await.unwrap();
+            assert_eq!(&hello[..3], &[0x01, 0x03, 0x00]);
+
+            let mut bytes = vec![0x04, 0x00, 0x03, b'r', b'i', b'o'];
+            bytes.extend(assignment("/Enabled", 1, 0, &[0x00, 1]));
+            bytes.push(0x03);
+            stream.write_all(&bytes).await.unwrap();
+
+            let mut complete = [0u8; 1];
+            stream.read_exact(&mut complete).await.unwrap();
+            assert_eq!(complete, [0x05]);
+
+            // an update, a flags update and a delete
+            stream
+                .write_all(&[0x11, 0, 1, 0, 2, 0x00, 0, 0x12, 0, 1, 1, 0x13, 0, 1])
+                .await
+                .unwrap();
+        });
+
+        let mut client = Nt3Client::connect(address, "test").await.unwrap();
+        let announce = |persistent| Nt3Event::Announce {
+            name: "/Enabled".to_string(),
+            value_type: "boolean".to_string(),
+            id: 1,
+            persistent,
+        };
+        assert_eq!(client.next().await, Some(announce(false)));
+        for value in [true, false] {
+            let Some(Nt3Event::Value {
+                name, value: got, ..
+            }) = client.next().await
+            else {
+                panic!("expected a value");
+            };
+            assert_eq!(
+                (name.as_str(), got),
+                ("/Enabled", TableValue::Boolean(value))
+            );
+        }
+        assert_eq!(client.next().await, Some(announce(true)));
+        assert_eq!(
+            client.next().await,
+            Some(Nt3Event::Unannounce(Some("/Enabled".to_string())))
+        );
+
+        server.await.unwrap();
+        assert_eq!(client.next().await, None);
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Cursor,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use network_tables::{rmpv, Value};
+use tokio::{
+    net::TcpStream,
+    time::{interval, timeout, Instant, Interval, MissedTickBehavior},
+};
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+/// The WebSocket subprotocol of NT4
+const PROTOCOL: &str = "networktables.first.wpi.edu";
+/// How often the server time is synced. It also keeps the connection alive
+const TIME_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+/// The connection is lost if the server sends nothing (not even a time sync answer) for this long
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the server gets to accept the connection
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The id of the time sync frames
+const TIME_SYNC_ID: i64 = -1;
+/// The type id of an int, which the time sync frames are sent as
+const INT_TYPE_ID: u8 = 2;
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+///
+/// # Function
+/// What happened to the topics of the NT4 server. The type strings are kept the way the server sent them, so types that
+/// are not built into NT4 (e.g. `struct:Pose2d`, `proto:wpi.proto.ProtobufPose2d` or `structschema`) still get through.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nt4Event {
+    /// A new topic, or a topic that got new properties. `properties` is the whole JSON object of the properties
+    Announce {
+        name: String,
+        value_type: String,
+        id: i32,
+        properties: serde_json::Value,
+    },
+    /// A new value of a topic. `type_id` is the NT4 type id of the data, `timestamp` is the server time (microseconds)
+    Value {
+        name: String,
+        value_type: String,
+        type_id: u8,
+        value: Value,
+        timestamp: u64,
+    },
+    /// A topic was unannounced
+    Unannounce(String),
+}
+
+#[derive(Debug, Clone)]
+struct Topic {
+    name: String,
+    value_type: String,
+    properties: serde_json::Value,
+    /// The timestamp of the newest value
+    last: Option<u64>,
+}
+
+///
+/// # Function
+/// An NT4 client (WebSocket + JSON text frames + MessagePack binary frames) that keeps the type strings of the topics as they
+/// are. It subscribes, gets the announcements and values, syncs the server time and can publish through an `Nt4Publisher`.
+///
+/// # Usage
+/// `Nt4Client::connect(address, identity)` makes the connection, `subscribe` once for every set of prefixes, then `next`
+/// gives the events until the connection is lost. The client does not reconnect by itself.
+///
+#[derive(Debug)]
+pub struct Nt4Client {
+    read: SplitStream<WebSocket>,
+    publisher: Nt4Publisher,
+    /// id -> topic
+    topics: HashMap<i32, Topic>,
+    events: VecDeque<Nt4Event>,
+    time_sync: Interval,
+    last_received: Instant,
+}
+
+///
+/// # Function
+/// Publishes topics and values on the connection of an `Nt4Client`. It is cheap to clone, every clone uses the same connection.
+///
+#[derive(Debug, Clone)]
+pub struct Nt4Publisher {
+    write: Arc<tokio::sync::Mutex<SplitSink<WebSocket, Message>>>,
+    next_uid: Arc<AtomicI64>,
+    start: Instant,
+    /// The server time minus the time since `start`, `None` until the first time sync answer
+    offset: Arc<Mutex<Option<i64>>>,
+}
+
+impl Nt4Client {
+    ///
+    /// # Function
+    /// Connects to an NT4 server.
+    ///
+    /// # Parameters
+    /// - `address`: The address of the server (port 5810 on a robot)
+    /// - `identity`: The name that the server shows for this client
+    ///
+    pub async fn connect(address: SocketAddr, identity: &str) -> Result<Self, String> {
+        let mut request = format!("ws://{}/nt/{}", address, identity)
+            .into_client_request()
+            .map_err(|err| err.to_string())?;
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(PROTOCOL));
+        let (websocket, _) = timeout(HANDSHAKE_TIMEOUT, tokio_tungstenite::connect_async(request))
+            .await
+            .map_err(|_| "Timed out while connecting".to_string())?
+            .map_err(|err| err.to_string())?;
+
+        let (write, read) = websocket.split();
+        let mut time_sync = interval(TIME_SYNC_INTERVAL);
+        time_sync.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Ok(Self {
+            read,
+            publisher: Nt4Publisher {
+                write: Arc::new(tokio::sync::Mutex::new(write)),
+                next_uid: Arc::new(AtomicI64::new(1)),
+                start: Instant::now(),
+                offset: Arc::new(Mutex::new(None)),
+            },
+            topics: HashMap::new(),
+            events: VecDeque::new(),
+            time_sync,
+            last_received: Instant::now(),
+        })
+    }
+
+    ///
+    /// # Function
+    /// Subscribes to topics.
+    ///
+    /// # Parameters
+    /// - `topics`: The topic names (or prefixes, if the options say so)
+    /// - `options`: The NT4 subscription options (e.g. `{"prefix": true, "all": true}`)
+    ///
+    pub async fn subscribe(
+        &self,
+        topics: &[String],
+        options: serde_json::Value,
+    ) -> Result<(), String> {
+        let subuid = self.publisher.next_uid.fetch_add(1, Ordering::Relaxed);
+        self.publisher
+            .send_text(serde_json::json!([{"method": "subscribe", "params": {
+                "topics": topics, "subuid": subuid, "options": options
+            }}]))
+            .await
+    }
+
+    pub fn publisher(&self) -> Nt4Publisher {
+        self.publisher.clone()
+    }
+
+    ///
+    /// # Function
+    /// Waits for the next event. The server time is synced while waiting.
+    ///
+    /// # Returns
+    /// `None` once the connection is lost
+    ///
+    pub async fn next(&mut self) -> Option<Nt4Event> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            tokio::select! {
+                message = self.read.next() => {
+                    self.last_received = Instant::now();
+                    match message {
+                        Some(Ok(Message::Text(text))) => self.handle_text(&text),
+                        Some(Ok(Message::Binary(data))) => self.handle_binary(&data),
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return None,
+                        Some(Ok(_)) => {}
+                    }
+                }
+                _ = self.time_sync.tick() => {
+                    if self.last_received.elapsed() > RECEIVE_TIMEOUT {
+                        return None;
+                    }
+                    self.publisher.sync_time().await.ok()?;
+                }
+            }
+        }
+    }
+
+    fn handle_text(&mut self, message: &str) {
+        let Ok(serde_json::Value::Array(messages)) = serde_json::from_str(message) else {
+            return;
+        };
+
+        for message in messages {
+            let params = &message["params"];
+            let name = params["name"].as_str().unwrap_or_default().to_string();
+            match message["method"].as_str().unwrap_or_default() {
+                "announce" => {
+                    let id = params["id"].as_i64().unwrap_or_default() as i32;
+                    let properties = match &params["properties"] {
+                        serde_json::Value::Object(properties) => {
+                            serde_json::Value::Object(properties.clone())
+                        }
+                        _ => serde_json::json!({}),
+                    };
+                    // an id that is in use is a new topic in its place
+                    if let Some(old) = self.topics.get(&id).filter(|old| old.name != name) {
+                        self.events
+                            .push_back(Nt4Event::Unannounce(old.name.clone()));
+                    }
+                    self.topics.insert(
+                        id,
+                        Topic {
+                            name: name.clone(),
+                            value_type: params["type"].as_str().unwrap_or_default().to_string(),
+                            properties,
+                            last: None,
+                        },
+                    );
+                    self.announce(id);
+                }
+                "unannounce" => {
+                    let id = params["id"].as_i64().unwrap_or_default() as i32;
+                    if let Some(topic) = self.topics.remove(&id) {
+                        self.events.push_back(Nt4Event::Unannounce(topic.name));
+                    }
+                }
+                "properties" => {
+                    let update = params["update"].as_object().cloned().unwrap_or_default();
+                    let Some((&id, topic)) =
+                        self.topics.iter_mut().find(|(_, topic)| topic.name == name)
+                    else {
+                        continue;
+                    };
+                    if let serde_json::Value::Object(properties) = &mut topic.properties {
+                        for (key, value) in update {
+                            if value.is_null() {
+                                properties.remove(&key);
+                            } else {
+                                properties.insert(key, value);
+                            }
+                        }
+                    }
+                    self.announce(id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_binary(&mut self, data: &[u8]) {
+        let mut cursor = Cursor::new(data);
+        while (cursor.position() as usize) < data.len() {
+            let Ok(Value::Array(mut frame)) = rmpv::decode::read_value(&mut cursor) else {
+                return;
+            };
+            if frame.len() != 4 {
+                continue;
+            }
+            let value = frame.pop().unwrap();
+            let (Some(id), Some(timestamp), Some(type_id)) =
+                (frame[0].as_i64(), frame[1].as_u64(), frame[2].as_u64())
+            else {
+                continue;
+            };
+
+            if id == TIME_SYNC_ID {
+                if let Some(sent) = value.as_i64() {
+                    self.publisher.set_server_time(timestamp, sent);
+                }
+                continue;
+            }
+            let Some(topic) = self.topics.get_mut(&(id as i32)) else {
+                continue;
+            };
+            // the server sends the newest value again for every subscription that matches, it is only given once
+            if topic.last == Some(timestamp) {
+                continue;
+            }
+            topic.last = Some(timestamp);
+            self.events.push_back(Nt4Event::Value {
+                name: topic.name.clone(),
+                value_type: topic.value_type.clone(),
+                type_id: type_id as u8,
+                value,
+                timestamp,
+            });
+        }
+    }
+
+    fn announce(&mut self, id: i32) {
+        let topic = &self.topics[&id];
+        self.events.push_back(Nt4Event::Announce {
+            name: topic.name.clone(),
+            value_type: topic.value_type.clone(),
+            id,
+            properties: topic.properties.clone(),
+        });
+    }
+}
+
+impl Nt4Publisher {
+    ///
+    /// # Function
+    /// Starts publishing a topic.
+    ///
+    /// # Parameters
+    /// - `name`: The topic name
+    /// - `value_type`: The NT4 type string of the topic (e.g. `double`)
+    ///
+    /// # Returns
+    /// The `pubuid` that the values and `unpublish` use
+    ///
+    pub async fn publish(&self, name: &str, value_type: &str) -> Result<i64, String> {
+        let pubuid = self.next_uid.fetch_add(1, Ordering::Relaxed);
+        self.send_text(serde_json::json!([{"method": "publish", "params": {
+            "name": name, "type": value_type, "pubuid": pubuid, "properties": {}
+        }}]))
+        .await?;
+        Ok(pubuid)
+    }
+
+    ///
+    /// # Function
+    /// Stops publishing a topic.
+    ///
+    pub async fn unpublish(&self, pubuid: i64) -> Result<(), String> {
+        self.send_text(serde_json::json!([{"method": "unpublish", "params": {"pubuid": pubuid}}]))
+            .await
+    }
+
+    ///
+    /// # Function
+    /// Sends a value of a published topic, with the server time of now (`0` if the server time is not known yet, which lets
+    /// the server use its own time).
+    ///
+    /// # Parameters
+    /// - `pubuid`: What `publish` returned
+    /// - `type_id`: The NT4 type id of the value
+    /// - `value`: The value
+    ///
+    pub async fn publish_value(
+        &self,
+        pubuid: i64,
+        type_id: u8,
+        value: Value,
+    ) -> Result<(), String> {
+        let time = self.server_time().unwrap_or(0);
+        self.send_binary(pubuid, time, type_id, value).await
+    }
+
+    /// The client time of the request is sent as the value, the server sends it back with its own time
+    async fn sync_time(&self) -> Result<(), String> {
+        let now = self.start.elapsed().as_micros() as i64;
+        self.send_binary(TIME_SYNC_ID, 0, INT_TYPE_ID, Value::from(now))
+            .await
+    }
+
+    /// Half of the round trip is added to the server time, like the NT4 clients of WPILib do
+    fn set_server_time(&self, server_time: u64, sent: i64) {
+        let now = self.start.elapsed().as_micros() as i64;
+        let round_trip = (now - sent).max(0);
+        if let Ok(mut offset) = self.offset.lock() {
+            *offset = Some(server_time as i64 + round_trip / 2 - now);
+        }
+    }
+
+    fn server_time(&self) -> Option<u64> {
+        let offset = (*self.offset.lock().ok()?)?;
+        Some((self.start.elapsed().as_micros() as i64 + offset).max(1) as u64)
+    }
+
+    async fn send_text(&self, message: serde_json::Value) -> Result<(), String> {
+        self.write
+            .lock()
+            .await
+            .send(Message::Text(message.to_string()))
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn send_binary(
+        &self,
+        id: i64,
+        time: u64,
+        type_id: u8,
+        value: Value,
+    ) -> Result<(), String> {
+        let mut buffer = Vec::new();
+        let frame = Value::Array(vec![
+            Value::from(id),
+            Value::from(time),
+            Value::from(type_id),
+            value,
+        ]);
+        rmpv::encode::write_value(&mut buffer, &frame).map_err(|err| err.to_string())?;
+        self.write
+            .lock()
+            .await
+            .send(Message::Binary(buffer))
+            .await
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_table_bridge::test_server::TestServer;
+
+    #[tokio::test]
+    async fn test_client_events() {
+        let server = TestServer::start().await;
+        server
+            .publish("/Drive/Pose", "struct:Pose2d", Value::Binary(vec![1, 2]))
+            .await;
+
+        let mut client = Nt4Client::connect(server.address(), "test").await.unwrap();
+        client
+            .subscribe(
+                &["/Drive/".to_string()],
+                serde_json::json!({"prefix": true}),
+            )
+            .await
+            .unwrap();
+
+        // the type string is kept, also if it is not an NT4 type
+        let Some(Nt4Event::Announce {
+            name, value_type, ..
+        }) = client.next().await
+        else {
+            panic!("expected an announcement");
+        };
+        assert_eq!(
+            (name.as_str(), value_type.as_str()),
+            ("/Drive/Pose", "struct:Pose2d")
+        );
+        let Some(Nt4Event::Value { type_id, value, .. }) = client.next().await else {
+            panic!("expected a value");
+        };
+        assert_eq!((type_id, value), (5, Value::Binary(vec![1, 2])));
+
+        // publishing goes through the same connection
+        let publisher = client.publisher();
+        let pubuid = publisher.publish("/Drive/Speed", "double").await.unwrap();
+        publisher
+            .publish_value(pubuid, 1, Value::F64(2.5))
+            .await
+            .unwrap();
+        let Some(Nt4Event::Announce { name, .. }) = client.next().await else {
+            panic!("expected an announcement");
+        };
+        assert_eq!(name, "/Drive/Speed");
+        // the server sends it back because of the subscription
+        let Some(Nt4Event::Value { name, value, .. }) = client.next().await else {
+            panic!("expected a value");
+        };
+        assert_eq!((name.as_str(), value), ("/Drive/Speed", Value::F64(2.5)));
+        assert_eq!(server.value("/Drive/Speed"), Some(Value::F64(2.5)));
+
+        server.unannounce("/Drive/Pose").await;
+        loop {
+            match client.next().await {
+                Some(Nt4Event::Unannounce(name)) => {
+                    assert_eq!(name, "/Drive/Pose");
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("the connection was lost"),
+            }
+        }
+
+        server.stop();
+        assert_eq!(client.next().await, None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::str::FromStr;
+
+///
+/// # Function
+/// A NetworkTables protocol that the bridge can talk.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// NT4 (WebSocket, port 5810 on a robot)
+    V4,
+    /// NT3 (TCP, port 1735 on a robot), for older robot firmware and coprocessors
+    V3,
+}
+
+///
+/// # Function
+/// Which protocols the bridge tries. `Auto` tries NT4 first and falls back to NT3 when NT4 does not work.
+///
+/// # Usage
+/// `ProtocolMode::from_str("auto")` (also `v4` and `v3`, case insensitive)
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub enum ProtocolMode {
+    V4,
+    V3,
+    #[default]
+    Auto,
+}
+
+impl ProtocolMode {
+    ///
+    /// # Function
+    /// Gets the protocols to try on a connection attempt, in order.
+    ///
+    /// # Parameters
+    /// - `last_working`: The protocol of the last connection that worked. In `Auto` mode it is tried first so a robot that
+    ///   only talks NT3 does not wait for an NT4 attempt on every reconnect
+    ///
+    pub fn attempts(self, last_working: Option<Protocol>) -> Vec<Protocol> {
+        match self {
+            ProtocolMode::V4 => vec![Protocol::V4],
+            ProtocolMode::V3 => vec![Protocol::V3],
+            ProtocolMode::Auto if last_working == Some(Protocol::V3) => {
+                vec![Protocol::V3, Protocol::V4]
+            }
+            ProtocolMode::Auto => vec![Protocol::V4, Protocol::V3],
+        }
+    }
+}
+
+impl FromStr for ProtocolMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "v4" | "nt4" => Ok(ProtocolMode::V4),
+            "v3" | "nt3" => Ok(ProtocolMode::V3),
+            "auto" => Ok(ProtocolMode::Auto),
+            _ => Err(format!("Unknown protocol mode: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ProtocolMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ProtocolMode::from_str("V4"), Ok(ProtocolMode::V4));
+        assert_eq!(ProtocolMode::from_str(" nt3 "), Ok(ProtocolMode::V3));
+        assert_eq!(ProtocolMode::from_str("auto"), Ok(ProtocolMode::Auto));
+        assert!(ProtocolMode::from_str("v2").is_err());
+    }
+
+    #[test]
+    fn test_attempts() {
+        assert_eq!(
+            ProtocolMode::V4.attempts(Some(Protocol::V3)),
+            vec![Protocol::V4]
+        );
+        assert_eq!(ProtocolMode::V3.attempts(None), vec![Protocol::V3]);
+        assert_eq!(
+            ProtocolMode::Auto.attempts(None),
+            vec![Protocol::V4, Protocol::V3]
+        );
+        assert_eq!(
+            ProtocolMode::Auto.attempts(Some(Protocol::V3)),
+            vec![Protocol::V3, Protocol::V4]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    future::Future,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+///
+/// # Function
+/// How long the bridge waits between connection attempts. The delay starts at `initial_delay` and is multiplied by
+/// `multiplier` after every failed attempt, up to `max_delay`. `jitter` then takes a random part off the delay so a room full
+/// of dashboards does not hit the robot at the exact same time.
+///
+/// # Fields
+/// - `initial_delay`: The delay after the first failed attempt (milliseconds)
+/// - `multiplier`: What the delay is multiplied by after every failed attempt
+/// - `max_delay`: The longest the delay can get (milliseconds)
+/// - `jitter`: The part of the delay that is random, between `0.0` (none) and `1.0` (anywhere between 0 and the delay)
+/// - `max_attempts`: Give up after this many failed attempts in a row. `None` never gives up
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: u64,
+    pub multiplier: f64,
+    pub max_delay: u64,
+    pub jitter: f64,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: 1000,
+            multiplier: 2.0,
+            max_delay: 30_000,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+///
+/// # Function
+/// Something that can wait. The bridge uses `TokioClock`, tests use a fake clock that only records how long it was asked to wait.
+///
+pub trait Clock {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()>;
+}
+
+///
+/// # Function
+/// The real clock, it waits with `tokio::time::sleep`.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> {
+        tokio::time::sleep(duration)
+    }
+}
+
+///
+/// # Function
+/// Keeps track of the failed attempts of one `ReconnectPolicy`.
+///
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    policy: ReconnectPolicy,
+    attempts: u32,
+    random_state: u64,
+}
+
+impl Backoff {
+    pub fn new(policy: ReconnectPolicy) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default();
+
+        Self::with_seed(policy, seed)
+    }
+
+    ///
+    /// # Function
+    /// Makes a backoff with a fixed seed for the jitter, so the delays are always the same.
+    ///
+    pub fn with_seed(policy: ReconnectPolicy, seed: u64) -> Self {
+        Self {
+            policy,
+            attempts: 0,
+            random_state: seed | 1, // xorshift gets stuck on 0
+        }
+    }
+
+    ///
+    /// # Function
+    /// Counts a failed attempt and gets how long to wait before the next one.
+    ///
+    /// # Returns
+    /// `None` if `max_attempts` has been reached and the bridge should give up
+    ///
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self
+            .policy
+            .max_attempts
+            .is_some_and(|max_attempts| self.attempts >= max_attempts)
+        {
+            return None;
+        }
+
+        let delay = (self.policy.initial_delay as f64
+            * self.policy.multiplier.max(1.0).powi(self.attempts as i32))
+        .min(self.policy.max_delay as f64);
+        let jitter = self.policy.jitter.clamp(0.0, 1.0) * self.random();
+        self.attempts = self.attempts.saturating_add(1);
+
+        Some(Duration::from_millis((delay * (1.0 - jitter)) as u64))
+    }
+
+    ///
+    /// # Function
+    /// Counts a failed attempt and waits before the next one.
+    ///
+    /// # Returns
+    /// `false` if `max_attempts` has been reached and the bridge should give up
+    ///
+    pub async fn wait(&mut self, clock: &impl Clock) -> bool {
+        match self.next_delay() {
+            Some(delay) => {
+                clock.sleep(delay).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Starts over from `initial_delay`. Called when a subscription works.
+    ///
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    /// A number between 0 and 1 (xorshift64, good enough for jitter)
+    fn random(&mut self) -> f64 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        (self.random_state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeClock {
+        slept: RefCell<Vec<Duration>>,
+    }
+
+    impl Clock for FakeClock {
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> {
+            self.slept.borrow_mut().push(duration);
+            std::future::ready(())
+        }
+    }
+
+    fn millis(delays: &[u64]) -> Vec<Duration> {
+        delays
+            .iter()
+            .map(|delay| Duration::from_millis(*delay))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_exponential_up_to_max_and_reset() {
+        let clock = FakeClock::default();
+        let mut backoff = Backoff::new(ReconnectPolicy {
+            initial_delay: 100,
+            multiplier: 2.0,
+            max_delay: 500,
+            jitter: 0.0,
+            max_attempts: None,
+        });
+
+        for _ in 0..5 {
+            assert!(backoff.wait(&clock).await);
+        }
+        backoff.reset();
+        assert!(backoff.wait(&clock).await);
+
+        assert_eq!(
+            *clock.slept.borrow(),
+            millis(&[100, 200, 400, 500, 500, 100])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_attempts() {
+        let clock = FakeClock::default();
+        let mut backoff = Backoff::new(ReconnectPolicy {
+            initial_delay: 10,
+            multiplier: 1.0,
+            jitter: 0.0,
+            max_attempts: Some(2),
+            ..Default::default()
+        });
+
+        assert!(backoff.wait(&clock).await);
+        assert!(backoff.wait(&clock).await);
+        assert!(!backoff.wait(&clock).await);
+        assert_eq!(*clock.slept.borrow(), millis(&[10, 10]));
+
+        backoff.reset();
+        assert!(backoff.wait(&clock).await);
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range() {
+        let policy = ReconnectPolicy {
+            initial_delay: 1000,
+            multiplier: 1.0,
+            jitter: 0.5,
+            ..Default::default()
+        };
+        let mut backoff = Backoff::with_seed(policy, 42);
+
+        let delays: Vec<Duration> = (0..50).map(|_| backoff.next_delay().unwrap()).collect();
+        assert!(delays
+            .iter()
+            .all(|delay| *delay >= Duration::from_millis(500)
+                && *delay <= Duration::from_millis(1000)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+
+        let mut same_seed = Backoff::with_seed(policy, 42);
+        assert_eq!(same_seed.next_delay().unwrap(), delays[0]);
+    }
+}
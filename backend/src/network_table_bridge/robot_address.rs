@@ -0,0 +1,121 @@
+use std::net::SocketAddr;
+
+///
+/// # Function
+/// Where the network table server is. This is what `NETWORK_TABLE_IP` is parsed into.
+///
+/// # Variants
+/// - `Team`: A FRC team number (e.g. `1234`). The usual robot addresses of that team are tried
+/// - `Host`: An IP (e.g. `10.12.34.2`) or a hostname (e.g. `localhost`, `roborio-1234-frc.local`) that is resolved by the system resolver
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum RobotAddress {
+    Team(u32),
+    Host(String),
+}
+
+impl RobotAddress {
+    ///
+    /// # Function
+    /// Reads an address from the env. Anything that is only digits is a team number, everything else is a host.
+    ///
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value.parse::<u32>() {
+            Ok(team) if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) => {
+                RobotAddress::Team(team)
+            }
+            _ => RobotAddress::Host(value.to_string()),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Gets the hosts to try, in order. For a team number this is the same list the WPILib dashboards use:
+    /// the static radio IP (`10.TE.AM.2`), mDNS, the USB address and the DNS names used on the field.
+    ///
+    pub fn candidates(&self) -> Vec<String> {
+        match self {
+            RobotAddress::Team(team) => vec![
+                format!("10.{}.{}.2", team / 100, team % 100),
+                format!("roborio-{}-frc.local", team),
+                "172.22.11.2".to_string(),
+                format!("roborio-{}-frc.lan", team),
+                format!("roborio-{}-frc.frc-field.local", team),
+            ],
+            RobotAddress::Host(host) => vec![host.clone()],
+        }
+    }
+}
+
+///
+/// # Function
+/// Resolves a host with the system resolver. IPs are returned as they are.
+///
+/// # Returns
+/// Every address the host resolved to, or the resolve error as a string (so it can be shown in the connection status)
+///
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| format!("Failed to resolve {}: {}", host, err))?
+        .collect();
+
+    if addresses.is_empty() {
+        return Err(format!("{} did not resolve to any address", host));
+    }
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(RobotAddress::parse("1234"), RobotAddress::Team(1234));
+        assert_eq!(
+            RobotAddress::parse("10.12.34.2"),
+            RobotAddress::Host("10.12.34.2".to_string())
+        );
+        assert_eq!(
+            RobotAddress::parse("roborio-1234-frc.local"),
+            RobotAddress::Host("roborio-1234-frc.local".to_string())
+        );
+        assert_eq!(
+            RobotAddress::parse("+12"),
+            RobotAddress::Host("+12".to_string())
+        );
+    }
+
+    #[test]
+    fn test_team_candidates() {
+        assert_eq!(
+            RobotAddress::Team(1234).candidates(),
+            vec![
+                "10.12.34.2",
+                "roborio-1234-frc.local",
+                "172.22.11.2",
+                "roborio-1234-frc.lan",
+                "roborio-1234-frc.frc-field.local"
+            ]
+        );
+        assert_eq!(RobotAddress::Team(254).candidates()[0], "10.2.54.2");
+        assert_eq!(RobotAddress::Team(12345).candidates()[0], "10.123.45.2");
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        assert_eq!(
+            resolve("127.0.0.1", 5810).await.unwrap(),
+            vec!["127.0.0.1:5810".parse().unwrap()]
+        );
+        assert!(resolve("localhost", 5810)
+            .await
+            .unwrap()
+            .iter()
+            .all(|address| address.ip().is_loopback()));
+        assert!(resolve("not a host", 5810).await.is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use super::topic_filter::TopicFilter;
+
+/// The prefix of the schema topics of the struct and protobuf topics (`/.schema/struct:*` and `/.schema/proto:*`)
+pub const SCHEMA_PREFIX: &str = "/.schema/";
+
+///
+/// # Function
+/// A set of topic prefixes that is subscribed to with its own NT4 options. This is how telemetry can be sampled (e.g. every
+/// 50 ms) while event topics still get every value. Every group is its own subscription on the same client.
+///
+/// # Fields
+/// - `name`: Only used in the logs
+/// - `prefixes`: The topic prefixes of the group (e.g. `["/SmartDashboard/Drive/"]`)
+/// - `periodic`: How often the server sends the newest value of a topic (milliseconds). `None` uses the server default (100 ms)
+/// - `all`: If the server should send every value instead of only the newest one per period
+/// - `topics_only`: Only get the announcements of the topics (for the topic catalog), no values
+///
+/// # Overlapping groups
+/// The server merges the options of overlapping subscriptions and sends every value once. A value is only recorded if the group
+/// with the longest matching prefix records values, so a `topics_only` group can leave out a part of a wider group.
+///
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubscriptionGroup {
+    #[serde(default)]
+    pub name: String,
+    pub prefixes: Vec<String>,
+    #[serde(default)]
+    pub periodic: Option<u64>,
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default)]
+    pub topics_only: bool,
+}
+
+impl SubscriptionGroup {
+    ///
+    /// # Function
+    /// The group that is used when no groups are configured: every prefix of the topic filter, with every value.
+    ///
+    pub fn default_for(topic_filter: &TopicFilter) -> Self {
+        Self {
+            name: "default".to_string(),
+            prefixes: topic_filter.subscription_prefixes(),
+            periodic: None,
+            all: true,
+            topics_only: false,
+        }
+    }
+
+    ///
+    /// # Function
+    /// The group of the schema topics, with every value. The struct and protobuf topics can only be decoded with their schemas,
+    /// so the bridge always subscribes to it, whatever the topic filter and the other groups say.
+    ///
+    pub fn schemas() -> Self {
+        Self {
+            name: "schemas".to_string(),
+            prefixes: vec![SCHEMA_PREFIX.to_string()],
+            periodic: None,
+            all: true,
+            topics_only: false,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Reads the groups from JSON (the format used in the .env file), e.g.
+    /// `[{"name": "telemetry", "prefixes": ["/SmartDashboard/"], "periodic": 50}, {"name": "events", "prefixes": ["/Events/"], "all": true}]`
+    ///
+    pub fn parse_list(json: &str) -> Result<Vec<Self>, serde_json::Error> {
+        if json.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        serde_json::from_str(json)
+    }
+
+    ///
+    /// # Function
+    /// Gets the NT4 options of the subscription, as they are sent in the `subscribe` message.
+    ///
+    pub fn options(&self) -> serde_json::Value {
+        let mut options = serde_json::json!({
+            "all": self.all,
+            "topicsonly": self.topics_only,
+            "prefix": true,
+        });
+        // NT4 wants `periodic` in seconds
+        if let Some(periodic) = self.periodic {
+            options["periodic"] = serde_json::json!(periodic as f64 / 1000.0);
+        }
+
+        options
+    }
+
+    fn longest_match(&self, topic: &str) -> Option<usize> {
+        self.prefixes
+            .iter()
+            .filter(|prefix| topic.starts_with(prefix.as_str()))
+            .map(String::len)
+            .max()
+    }
+}
+
+///
+/// # Function
+/// Finds the group that a topic belongs to: the one with the longest matching prefix (the first one if there is a tie).
+///
+/// # Returns
+/// The index of the group, `None` if no group matches
+///
+pub fn owner(groups: &[SubscriptionGroup], topic: &str) -> Option<usize> {
+    groups
+        .iter()
+        .enumerate()
+        .filter_map(|(index, group)| group.longest_match(topic).map(|length| (index, length)))
+        .fold(
+            None,
+            |best: Option<(usize, usize)>, (index, length)| match best {
+                Some((_, best_length)) if best_length >= length => best,
+                _ => Some((index, length)),
+            },
+        )
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_options() {
+        let groups = SubscriptionGroup::parse_list(
+            r#"[{"name": "telemetry", "prefixes": ["/SmartDashboard/"], "periodic": 50}, {"prefixes": ["/Events/"], "all": true}]"#,
+        )
+        .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].periodic, Some(50));
+        assert!(groups[1].all);
+        assert!(SubscriptionGroup::parse_list("").unwrap().is_empty());
+        assert!(SubscriptionGroup::parse_list("{").is_err());
+
+        let options = groups[0].options();
+        assert_eq!(options["periodic"], serde_json::json!(0.05));
+        assert_eq!(options["all"], serde_json::json!(false));
+        assert_eq!(options["topicsonly"], serde_json::json!(false));
+        assert_eq!(options["prefix"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn test_owner() {
+        let groups = vec![
+            SubscriptionGroup {
+                prefixes: vec!["/".to_string()],
+                ..Default::default()
+            },
+            SubscriptionGroup {
+                prefixes: vec!["/Events/".to_string(), "/FMSInfo/".to_string()],
+                all: true,
+                ..Default::default()
+            },
+            SubscriptionGroup {
+                prefixes: vec!["/Events/".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(owner(&groups, "/Drive/Speed"), Some(0));
+        assert_eq!(owner(&groups, "/Events/Intake"), Some(1));
+        assert_eq!(owner(&groups, "/FMSInfo/MatchNumber"), Some(1));
+        assert_eq!(owner(&groups, "NoSlash"), None);
+    }
+}
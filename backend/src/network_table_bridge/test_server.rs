@@ -0,0 +1,95 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use network_tables::Value;
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::source::nt4_server::Nt4Server;
+
+///
+/// # Function
+/// The NT4 server of the `nt4-server` source for tests, without recording. The test plays the robot: it publishes and
+/// unannounces the topics and reads what the bridge wrote.
+///
+/// # Usage
+/// `TestServer::start().await` binds to an ephemeral port. `stop` drops every client (like a robot that was turned off)
+/// and `TestServer::start_on(address)` brings the server back on the same port.
+///
+#[derive(Debug)]
+pub struct TestServer {
+    address: SocketAddr,
+    server: Nt4Server,
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::start_on("127.0.0.1:0".parse().unwrap()).await
+    }
+
+    pub async fn start_on(address: SocketAddr) -> Self {
+        let listener = TcpListener::bind(address).await.unwrap();
+        let test_server = Self {
+            address: listener.local_addr().unwrap(),
+            server: Nt4Server::new(None),
+            tasks: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let server = test_server.server.clone();
+        let tasks = test_server.tasks.clone();
+        let accept = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let client = tokio::spawn(server.clone().handle_client(stream));
+                tasks.lock().unwrap().push(client);
+            }
+        });
+        test_server.tasks.lock().unwrap().push(accept);
+
+        test_server
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    ///
+    /// # Function
+    /// Sets the value of a topic (announcing it first if it is new) and sends it to every subscribed client.
+    ///
+    /// # Parameters
+    /// - `name`: The topic name
+    /// - `r#type`: The NT4 type string (e.g. `double`)
+    /// - `value`: The value
+    ///
+    pub async fn publish(&self, name: &str, r#type: &str, value: Value) {
+        self.server.publish(name, r#type, value).await;
+    }
+
+    ///
+    /// # Function
+    /// Removes a topic and tells the clients that knew about it.
+    ///
+    pub async fn unannounce(&self, name: &str) {
+        self.server.unannounce(name).await;
+    }
+
+    ///
+    /// # Function
+    /// Gets the newest value of a topic, also if it was published by a client.
+    ///
+    pub fn value(&self, name: &str) -> Option<Value> {
+        self.server.value(name)
+    }
+
+    ///
+    /// # Function
+    /// Stops listening and drops every client connection.
+    ///
+    pub fn stop(self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,161 @@
+///
+/// # Function
+/// Decides which network table topics get recorded. It is built from an allowlist and a denylist of patterns.
+///
+/// # Patterns
+/// - A pattern without any `*` or `?` is a prefix. `/SmartDashboard/` matches every topic under `/SmartDashboard/`.
+/// - `*` matches anything inside one level of the topic (it does not go past a `/`). `/SmartDashboard/Drive/*` matches `/SmartDashboard/Drive/Speed` but not `/SmartDashboard/Drive/Module/Angle`.
+/// - `**` matches anything, including `/`. `/SmartDashboard/**/Angle` matches `/SmartDashboard/Drive/Module/Angle`.
+/// - `?` matches one character that is not a `/`.
+///
+/// # Rules
+/// A topic is recorded if the allowlist is empty or one of the allow patterns matches, AND none of the deny patterns match.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl TopicFilter {
+    pub fn new(allow: Vec<String>, deny: Vec<String>) -> Self {
+        Self { allow, deny }
+    }
+
+    ///
+    /// # Function
+    /// Makes a filter from two comma separated lists of patterns (the format used in the .env file). Empty entries are ignored.
+    ///
+    /// # Parameters
+    /// - `allow`: e.g. `/SmartDashboard/Drive/*,/FMSInfo/`
+    /// - `deny`: e.g. `/SmartDashboard/Drive/Debug/**`
+    ///
+    pub fn from_lists(allow: &str, deny: &str) -> Self {
+        Self::new(split_list(allow), split_list(deny))
+    }
+
+    ///
+    /// # Function
+    /// Checks if a topic should be recorded.
+    ///
+    pub fn matches(&self, topic: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|pattern| matches(pattern, topic)))
+            && !self.deny.iter().any(|pattern| matches(pattern, topic))
+    }
+
+    ///
+    /// # Function
+    /// Checks if there is at least one allow pattern. Without one the filter matches every topic.
+    ///
+    pub fn has_allowlist(&self) -> bool {
+        !self.allow.is_empty()
+    }
+
+    ///
+    /// # Function
+    /// Gets the prefixes that should be sent in the NT4 subscribe message (with `prefix: true`). This is the part of every allow
+    /// pattern before its first wildcard, so the robot only sends us topics that can possibly match. The exact matching is
+    /// then done by `matches`.
+    ///
+    /// # Returns
+    /// `[""]` (everything) if the allowlist is empty.
+    ///
+    pub fn subscription_prefixes(&self) -> Vec<String> {
+        if self.allow.is_empty() {
+            return vec!["".to_string()];
+        }
+
+        let mut prefixes: Vec<String> = self
+            .allow
+            .iter()
+            .map(|pattern| {
+                pattern
+                    .split(['*', '?'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect();
+
+        prefixes.sort();
+        prefixes.dedup();
+        prefixes
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn matches(pattern: &str, topic: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return topic.starts_with(pattern);
+    }
+
+    glob_matches(pattern.as_bytes(), topic.as_bytes())
+}
+
+fn glob_matches(pattern: &[u8], topic: &[u8]) -> bool {
+    match pattern {
+        [] => topic.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=topic.len()).any(|i| glob_matches(rest, &topic[i..])),
+        [b'*', rest @ ..] => {
+            let level_end = topic.iter().position(|c| *c == b'/').unwrap_or(topic.len());
+            (0..=level_end).any(|i| glob_matches(rest, &topic[i..]))
+        }
+        [b'?', rest @ ..] => {
+            !topic.is_empty() && topic[0] != b'/' && glob_matches(rest, &topic[1..])
+        }
+        [c, rest @ ..] => !topic.is_empty() && topic[0] == *c && glob_matches(rest, &topic[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let filter = TopicFilter::default();
+        assert!(filter.matches("/SmartDashboard/Drive/Speed"));
+        assert_eq!(filter.subscription_prefixes(), vec!["".to_string()]);
+    }
+
+    #[test]
+    fn test_prefix_and_glob() {
+        let filter = TopicFilter::from_lists("/SmartDashboard/Drive/*, /FMSInfo/", "");
+        assert!(filter.matches("/SmartDashboard/Drive/Speed"));
+        assert!(!filter.matches("/SmartDashboard/Drive/Module/Angle"));
+        assert!(!filter.matches("/SmartDashboard/Arm/Angle"));
+        assert!(filter.matches("/FMSInfo/IsRedAlliance"));
+        assert_eq!(
+            filter.subscription_prefixes(),
+            vec![
+                "/FMSInfo/".to_string(),
+                "/SmartDashboard/Drive/".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_double_star_and_question_mark() {
+        let filter = TopicFilter::from_lists("/SmartDashboard/**/Angle,/Module?", "");
+        assert!(filter.matches("/SmartDashboard/Drive/Module/Angle"));
+        assert!(filter.matches("/Module1"));
+        assert!(!filter.matches("/Module/"));
+    }
+
+    #[test]
+    fn test_denylist() {
+        let filter =
+            TopicFilter::from_lists("/SmartDashboard/", "/SmartDashboard/Debug/**,/*/Noise");
+        assert!(filter.matches("/SmartDashboard/Drive/Speed"));
+        assert!(!filter.matches("/SmartDashboard/Debug/Loop/Time"));
+        assert!(!filter.matches("/SmartDashboard/Noise"));
+        assert!(filter.matches("/SmartDashboard/Drive/Noise"));
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use network_tables::v4::Type;
+
+use super::{nt4::Nt4Publisher, topic_filter::TopicFilter};
+use crate::database::{
+    now_micros,
+    structs::{table_value::TableValue, write_record::WriteRecord},
+    SQLiteDatabase,
+};
+
+///
+/// # Function
+/// A value that the dashboard wants to write to the network table. It is the body of `POST /network-table/write` and the
+/// `data` of the `write` WebSocket message.
+///
+/// # Fields
+/// - `topic`: The topic to write to
+/// - `value_type`: The NT4 type of the value (written as `type` in JSON)
+/// - `value`: The value, as JSON (e.g. `0.05`, `true`, `[1, 2]`)
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct WriteRequest {
+    pub topic: String,
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub value: serde_json::Value,
+}
+
+///
+/// # Function
+/// Why a write did not happen.
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum WriteError {
+    /// The topic does not match `NETWORK_TABLE_WRITABLE_TOPICS`
+    TopicNotWritable,
+    /// The type is not a NT4 type
+    InvalidType,
+    /// The value does not fit the type
+    InvalidValue,
+    /// The bridge is not connected to the network table right now
+    NotConnected,
+    /// The network table client returned an error
+    PublishFailed(String),
+}
+
+#[derive(Debug, Default)]
+struct WriterState {
+    publisher: Option<Nt4Publisher>,
+    /// The topics this connection has published, with the type they were published with and their `pubuid`
+    topics: HashMap<String, (Type, i64)>,
+}
+
+///
+/// # Function
+/// Publishes values from the dashboard to the network table through the bridge's client. Only topics matching the writable
+/// list can be written and every write (also refused ones) is added to the audit log in the database. It is cheap to clone.
+///
+#[derive(Debug, Clone)]
+pub struct NetworkTableWriter {
+    writable: TopicFilter,
+    state: Arc<tokio::sync::Mutex<WriterState>>,
+    database: Arc<Mutex<SQLiteDatabase>>,
+}
+
+impl NetworkTableWriter {
+    ///
+    /// # Function
+    /// Makes a writer that is not connected yet.
+    ///
+    /// # Parameters
+    /// - `writable`: The topics that can be written. Nothing can be written if its allowlist is empty
+    /// - `database`: The database that the audit log is written to
+    ///
+    pub fn new(writable: TopicFilter, database: Arc<Mutex<SQLiteDatabase>>) -> Self {
+        Self {
+            writable,
+            state: Arc::new(tokio::sync::Mutex::new(WriterState::default())),
+            database,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Called by the bridge when it connects (`Some`) and when the connection is gone (`None`).
+    ///
+    pub async fn set_publisher(&self, publisher: Option<Nt4Publisher>) {
+        let mut state = self.state.lock().await;
+        state.publisher = publisher;
+        state.topics.clear();
+    }
+
+    ///
+    /// # Function
+    /// Checks if a topic can be written.
+    ///
+    pub fn is_writable(&self, topic: &str) -> bool {
+        self.writable.has_allowlist() && self.writable.matches(topic)
+    }
+
+    ///
+    /// # Function
+    /// Publishes a value to the network table and adds the write to the audit log.
+    ///
+    /// # Parameters
+    /// - `request`: What to write
+    /// - `origin`: Where the write came from (`http` or `websocket`), for the audit log
+    /// - `client`: The address of the client that asked for the write, for the audit log
+    ///
+    pub async fn write(
+        &self,
+        request: WriteRequest,
+        origin: &str,
+        client: Option<String>,
+    ) -> Result<(), WriteError> {
+        let r#type = Type::from_str(&request.value_type);
+        let value = r#type.and_then(|r#type| TableValue::from_json(r#type, &request.value));
+
+        let result = match (r#type, &value) {
+            _ if !self.is_writable(&request.topic) => Err(WriteError::TopicNotWritable),
+            (None, _) => Err(WriteError::InvalidType),
+            (_, None) => Err(WriteError::InvalidValue),
+            (Some(r#type), Some(value)) => self.publish(&request.topic, r#type, value).await,
+        };
+
+        // a value that does not fit the type is logged as its JSON string, so it can be read back
+        let (value_type, value) = match value {
+            Some(value) => (request.value_type, value),
+            None => (
+                "string".to_string(),
+                TableValue::String(request.value.to_string()),
+            ),
+        };
+        let record = WriteRecord {
+            time: now_micros(),
+            topic: request.topic,
+            value_type,
+            value,
+            origin: origin.to_string(),
+            client,
+            error: result.as_ref().err().map(|err| format!("{:?}", err)),
+        };
+        if let Ok(database) = self.database.lock() {
+            let _ = database.add_write(&record);
+        }
+
+        result
+    }
+
+    async fn publish(
+        &self,
+        topic: &str,
+        r#type: Type,
+        value: &TableValue,
+    ) -> Result<(), WriteError> {
+        let mut state = self.state.lock().await;
+        let Some(publisher) = state.publisher.clone() else {
+            return Err(WriteError::NotConnected);
+        };
+
+        let pubuid = match state.topics.get(topic) {
+            Some((published_type, pubuid)) if published_type.as_str() == r#type.as_str() => *pubuid,
+            old => {
+                if let Some((_, old)) = old.copied() {
+                    let _ = publisher.unpublish(old).await;
+                }
+
+                let pubuid = publisher
+                    .publish(topic, r#type.as_str())
+                    .await
+                    .map_err(WriteError::PublishFailed)?;
+                state.topics.insert(topic.to_string(), (r#type, pubuid));
+                pubuid
+            }
+        };
+
+        publisher
+            .publish_value(pubuid, r#type.as_u8(), value.to_message())
+            .await
+            .map_err(WriteError::PublishFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_writer() -> NetworkTableWriter {
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        database.clear_database().unwrap();
+        NetworkTableWriter::new(
+            TopicFilter::from_lists("/Tuning/", ""),
+            Arc::new(Mutex::new(database)),
+        )
+    }
+
+    fn request(topic: &str, value_type: &str, value: serde_json::Value) -> WriteRequest {
+        WriteRequest {
+            topic: topic.to_string(),
+            value_type: value_type.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_empty_allowlist_is_read_only() {
+        let database = Arc::new(Mutex::new(SQLiteDatabase::new("test.db", 2).unwrap()));
+        let writer = NetworkTableWriter::new(TopicFilter::default(), database);
+        assert!(!writer.is_writable("/Tuning/kP"));
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_refused_writes_are_audited() {
+        let writer = get_writer();
+
+        assert_eq!(
+            writer
+                .write(
+                    request("/Drive/Speed", "double", serde_json::json!(1)),
+                    "http",
+                    None
+                )
+                .await,
+            Err(WriteError::TopicNotWritable)
+        );
+        assert_eq!(
+            writer
+                .write(
+                    request("/Tuning/kP", "double", serde_json::json!("abc")),
+                    "http",
+                    None
+                )
+                .await,
+            Err(WriteError::InvalidValue)
+        );
+        assert_eq!(
+            writer
+                .write(
+                    request("/Tuning/kP", "double", serde_json::json!(0.5)),
+                    "websocket",
+                    Some("127.0.0.1:1234".to_string())
+                )
+                .await,
+            Err(WriteError::NotConnected)
+        );
+
+        let writes = writer.database.lock().unwrap().get_writes(3).unwrap();
+        assert_eq!(writes[0].topic, "/Tuning/kP");
+        assert_eq!(writes[0].value, TableValue::Double(0.5));
+        assert_eq!(writes[0].origin, "websocket");
+        assert_eq!(writes[0].client, Some("127.0.0.1:1234".to_string()));
+        assert_eq!(writes[0].error, Some("NotConnected".to_string()));
+        assert_eq!(writes[1].value_type, "string");
+        assert_eq!(writes[1].value, TableValue::String("\"abc\"".to_string()));
+        assert_eq!(writes[2].error, Some("TopicNotWritable".to_string()));
+    }
+}
@@ -5,11 +5,25 @@ use std::{
 
 use api::database::{
     clean_whole_db::clean_whole_database, clear_database::clear_database, get_entries::get_entries,
-    get_entry::get_entry, get_entry_and_clean::get_entry_and_clean,
+    get_entry::get_entry, get_entry_and_clean::get_entry_and_clean, get_raw::get_raw,
+    get_sessions::get_sessions, get_topic_tree::get_topic_tree, get_topics::get_topics,
+    get_writes::get_writes,
+};
+use api::network_table::write::write_value;
+use api::status::{
+    ingest::get_ingest_status,
+    network_table::{get_network_table_status, get_network_tables_status},
 };
 use rocket::{Config, Ignite, Rocket};
 
-use crate::database::SQLiteDatabase;
+use crate::{
+    database::SQLiteDatabase,
+    ingest::IngestCounters,
+    network_table_bridge::{
+        connection_status::{ConnectionTracker, ConnectionTrackers},
+        writer::NetworkTableWriter,
+    },
+};
 
 mod api;
 
@@ -19,6 +33,10 @@ mod api;
 ///
 /// # Parameters
 /// - `database_instance`: An `Arc<Mutex<SQLiteDatabase>>` that will be used to communicate with the database. That should be a single instance of the DB.
+/// - `ingest_counters`: The counters of the ingestion pipeline, served on `/status/ingest`
+/// - `connection`: The network table connection status, served on `/status/network-table`
+/// - `connections`: The status of every network table connection, served on `/status/network-tables`
+/// - `writer`: Writes values from the dashboard to the network table, used by `/network-table/write`
 ///
 /// # Usage
 /// This function is there to simplify the code of the main function. If I were to put the whole code in the main function, it would become too big and unreadable.
@@ -37,6 +55,10 @@ mod api;
 ///
 pub fn rocket_launch(
     database_instance: &Arc<Mutex<SQLiteDatabase>>,
+    ingest_counters: Arc<IngestCounters>,
+    connection: ConnectionTracker,
+    connections: ConnectionTrackers,
+    writer: NetworkTableWriter,
     port: u16,
 ) -> impl Future<Output = Result<Rocket<Ignite>, rocket::Error>> {
     let database_instance = database_instance.clone();
@@ -46,6 +68,10 @@ pub fn rocket_launch(
     };
     rocket::custom(config)
         .manage(database_instance)
+        .manage(ingest_counters)
+        .manage(connection)
+        .manage(connections)
+        .manage(writer)
         .mount(
             "/",
             routes![
@@ -53,7 +79,16 @@ pub fn rocket_launch(
                 clean_whole_database,
                 get_entry_and_clean,
                 get_entries,
-                clear_database
+                get_raw,
+                clear_database,
+                get_sessions,
+                get_ingest_status,
+                get_network_table_status,
+                get_network_tables_status,
+                get_topic_tree,
+                get_topics,
+                get_writes,
+                write_value
             ],
         )
         .launch()
@@ -1,3 +1,5 @@
 // This file is just to make everything look pretty
 
 pub mod database;
+pub mod network_table;
+pub mod status;
@@ -3,8 +3,17 @@
 pub mod clean_whole_db;
 pub mod clear_database;
 pub mod codes;
+#[cfg(test)]
 pub mod data_struct;
 pub mod get_entries;
 pub mod get_entry;
 pub mod get_entry_and_clean;
+pub mod get_raw;
+pub mod get_sessions;
+pub mod get_topic_tree;
+pub mod get_topics;
+pub mod get_writes;
+#[cfg(test)]
 pub mod test_util;
+pub mod time_base;
+pub mod time_unit;
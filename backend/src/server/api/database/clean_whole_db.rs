@@ -2,7 +2,7 @@ use std::sync::{Arc, Mutex};
 
 use rocket::{serde::json::Json, State};
 
-use crate::database::{self, SQLiteDatabase};
+use crate::database::SQLiteDatabase;
 
 use super::codes::{self, Success};
 
@@ -30,14 +30,12 @@ pub fn clean_whole_database(
 
     let _ = database.unwrap().clean_database();
 
-    return Json(Ok(codes::Success::DatabaseCleaningSuccess()));
+    Json(Ok(codes::Success::DatabaseCleaningSuccess()))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{os::unix::thread, thread::spawn};
-
-    use rocket::{http::ContentType, local::blocking::Client};
+    use rocket::local::blocking::Client;
 
     use crate::server::api::database::test_util;
 
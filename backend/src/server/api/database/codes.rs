@@ -6,16 +6,20 @@ use serde::{Deserialize, Serialize};
 ///
 // this code essentially says that this enum can be deserialized from a json string
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[allow(clippy::enum_variant_names)] // the variant names are sent to the frontend
 pub enum Error {
     DatabasePoisonedError(i32),
     DatabaseInvalidAmountError(i32),
+    DatabaseQueryError(i32),
 }
 
 impl Error {
+    #[allow(clippy::wrong_self_convention)]
     pub fn new(&self) -> Self {
         match self {
             Error::DatabasePoisonedError(_) => Error::DatabasePoisonedError(0),
             Error::DatabaseInvalidAmountError(_) => Error::DatabaseInvalidAmountError(1),
+            Error::DatabaseQueryError(_) => Error::DatabaseQueryError(2),
         }
     }
 }
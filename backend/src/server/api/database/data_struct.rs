@@ -5,5 +5,5 @@
 pub struct Topic {
     pub topic: String,
     pub amount: Option<u32>, // option means that the value can be none or does not exist
-    pub time_since_last_update: Option<u32>, // option means that the value can be none or does not exist
+    pub time_since_last_update: Option<u64>, // option means that the value can be none or does not exist
 }
@@ -2,12 +2,9 @@ use std::sync::{Arc, Mutex};
 
 use rocket::{serde::json::Json, State};
 
-use crate::{
-    database::{self, structs::table_entree::TableEntree, SQLiteDatabase},
-    server::api::database::data_struct::Topic,
-};
+use crate::database::{now_micros, structs::table_entree::TableEntree, SQLiteDatabase};
 
-use super::codes;
+use super::{codes, time_base::TimeBase, time_unit::TimeUnit};
 
 ///
 /// # Function
@@ -15,6 +12,12 @@ use super::codes;
 ///
 /// # Parameters
 /// - `table_topic`: A `Json<Topic>` that contains the topic to get from the database
+/// - `unit`: The unit of `time_since_last_update` and of the returned timestamps. Milliseconds by default. OPTIONAL
+/// - `session`: The id of the recording session to get the entries from. The current session by default. OPTIONAL
+/// - `time_base`: `wall` to get the entries of every session by wall clock time, `time_since_last_update` is then counted
+///   back from now and `session` is not used. `robot` by default. OPTIONAL
+/// - `source`: Get the entries of this network table connection (e.g. `sim`). The connections have their own clocks, so by
+///   default the entries of the connection that updated the topic last are returned (with `time_base=wall`: of every connection). OPTIONAL
 /// - `database`: The database that will be used to get the data
 ///     - note that the database param is passed into the function by default
 ///
@@ -23,11 +26,18 @@ use super::codes;
 ///
 
 // This code essentially means that the "get_entries" function will be called when you make an api request to the /get-entries endpoint.
-#[get("/get-entries?<topic>&<amount>&<time_since_last_update>")]
+#[get(
+    "/get-entries?<topic>&<amount>&<time_since_last_update>&<unit>&<session>&<time_base>&<source>"
+)]
+#[allow(clippy::too_many_arguments)] // every query parameter is an argument
 pub fn get_entries(
     topic: String,
     amount: Option<u32>,
-    time_since_last_update: Option<u32>,
+    time_since_last_update: Option<u64>,
+    unit: Option<TimeUnit>,
+    session: Option<i64>,
+    time_base: Option<TimeBase>,
+    source: Option<String>,
     database: &State<Arc<Mutex<SQLiteDatabase>>>,
 ) -> Json<Result<Vec<TableEntree>, codes::Error>> {
     let database = database.lock();
@@ -46,23 +56,40 @@ pub fn get_entries(
 
     let database = database.unwrap();
     let amount = amount.unwrap();
-    let values = if let Some(time) = time_since_last_update {
-        database.get_values(&topic, time, amount)
-    } else {
-        database.get_values_no_time(&topic, amount)
+    let unit = unit.unwrap_or_default();
+    let values = match time_base.unwrap_or_default() {
+        TimeBase::Wall => database.get_wall_time_values(
+            &topic,
+            source.as_deref(),
+            time_since_last_update
+                .map_or(0, |time| now_micros().saturating_sub(unit.to_micros(time))),
+            amount,
+        ),
+        TimeBase::Robot => database.get_session_values(
+            session.unwrap_or(database.current_session()),
+            &topic,
+            source.as_deref(),
+            time_since_last_update.map_or(u64::MAX, |time| unit.to_micros(time)),
+            amount,
+        ),
     };
 
-    Json(Ok(values.unwrap_or(vec![])))
+    Json(Ok(values
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entree| unit.convert_entree(entree))
+        .collect()))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{os::unix::thread, thread::spawn};
-
     use codes::Success;
     use rocket::{http::ContentType, local::blocking::Client};
 
-    use crate::server::api::database::test_util;
+    use crate::{
+        database::structs::table_value::TableValue,
+        server::api::database::{data_struct::Topic, test_util},
+    };
 
     use super::*;
 
@@ -157,9 +184,21 @@ mod tests {
 
         let body = response.into_string().unwrap();
         let expected: Result<Vec<TableEntree>, codes::Error> = Ok(vec![
-            TableEntree::new("test".to_string(), "test".to_string(), 4),
-            TableEntree::new("test".to_string(), "test".to_string(), 3),
-            TableEntree::new("test".to_string(), "test".to_string(), 2),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                4,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                3,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                2,
+            ),
         ]);
 
         assert_eq!(body, serde_json::to_string(&expected).unwrap());
@@ -190,13 +229,185 @@ mod tests {
 
         let body = response.into_string().unwrap();
         let expected: Result<Vec<TableEntree>, codes::Error> = Ok(vec![
-            TableEntree::new("test".to_string(), "test".to_string(), 4),
-            TableEntree::new("test".to_string(), "test".to_string(), 3),
-            TableEntree::new("test".to_string(), "test".to_string(), 2),
-            TableEntree::new("test".to_string(), "test".to_string(), 1),
-            TableEntree::new("test".to_string(), "test".to_string(), 0),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                4,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                3,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                2,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                1,
+            ),
+            TableEntree::new(
+                "test".to_string(),
+                TableValue::String("test".to_string()),
+                0,
+            ),
         ]);
 
         assert_eq!(body, serde_json::to_string(&expected).unwrap());
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_get_amount_with_time_microseconds() {
+        let database = Arc::new(Mutex::new(test_util::put_data_in_database(
+            test_util::get_database(2),
+            5,
+            1,
+        )));
+        let rocket = test_util::get_rocket_build(database.clone());
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let response = client
+            .get("/get-entries?topic=test&amount=5&time_since_last_update=2000&unit=us")
+            .header(ContentType::JSON)
+            .dispatch();
+
+        let body = response.into_string().unwrap();
+        let expected: Result<Vec<TableEntree>, codes::Error> = Ok([4000, 3000, 2000]
+            .into_iter()
+            .map(|timestamp| {
+                TableEntree::new(
+                    "test".to_string(),
+                    TableValue::String("test".to_string()),
+                    timestamp,
+                )
+            })
+            .collect());
+
+        assert_eq!(body, serde_json::to_string(&expected).unwrap());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_get_typed_values() {
+        let mut database = test_util::get_database(2);
+        database
+            .add_value(TableEntree::new(
+                "typed".to_string(),
+                TableValue::DoubleArray(vec![1.5, 2.0]),
+                1000,
+            ))
+            .unwrap();
+        database
+            .add_value(TableEntree::new(
+                "typed".to_string(),
+                TableValue::Boolean(true),
+                2000,
+            ))
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let response = client
+            .get(test_util::to_get_request(
+                Topic {
+                    topic: "typed".to_string(),
+                    amount: Some(2),
+                    time_since_last_update: None,
+                },
+                "/get-entries",
+            ))
+            .header(ContentType::JSON)
+            .dispatch();
+
+        let body = response.into_string().unwrap();
+        assert_eq!(
+            body,
+            r#"{"Ok":[{"topic":"typed","type":"boolean","value":true,"timestamp":2},{"topic":"typed","type":"double[]","value":[1.5,2.0],"timestamp":1}]}"#
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_get_by_wall_time() {
+        let mut database = test_util::get_database(2);
+        let now = now_micros();
+        for (timestamp, wall_time) in [(5, now - 20_000_000), (1, now - 2_000_000)] {
+            let mut entree =
+                TableEntree::new("wall".to_string(), TableValue::Double(1.0), timestamp);
+            entree.wall_time = Some(wall_time);
+            database.add_value(entree).unwrap();
+            database.start_session().unwrap(); // the robot rebooted
+        }
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let response = client
+            .get("/get-entries?topic=wall&amount=5&time_base=wall&time_since_last_update=10000&unit=ms")
+            .dispatch();
+        let body: Result<Vec<TableEntree>, codes::Error> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let body = body.unwrap();
+        assert_eq!(body.len(), 1);
+        assert_eq!(body[0].timestamp, 0);
+        assert_eq!(body[0].wall_time, Some((now - 2_000_000) / 1000));
+
+        let response = client
+            .get("/get-entries?topic=wall&amount=5&time_base=wall&unit=us")
+            .dispatch();
+        let body: Result<Vec<TableEntree>, codes::Error> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        let timestamps: Vec<u64> = body
+            .unwrap()
+            .iter()
+            .map(|entree| entree.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![1, 5]);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_get_by_source() {
+        let mut database = test_util::get_database(2);
+        for (source, value) in [("robot", 1.0), ("sim", 2.0)] {
+            let mut entree =
+                TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(value), 1);
+            entree.source = Some(source.to_string());
+            database.add_value(entree).unwrap();
+        }
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let values = |query: &str| {
+            let body: Result<Vec<TableEntree>, codes::Error> = serde_json::from_str(
+                &client
+                    .get(format!("/get-entries?topic=/Drive/Speed&amount=5{}", query))
+                    .dispatch()
+                    .into_string()
+                    .unwrap(),
+            )
+            .unwrap();
+            body.unwrap()
+                .into_iter()
+                .map(|entree| (entree.source.unwrap(), entree.value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            values("&source=sim"),
+            vec![("sim".to_string(), TableValue::Double(2.0))]
+        );
+        // the connection that updated the topic last
+        assert_eq!(
+            values(""),
+            vec![("sim".to_string(), TableValue::Double(2.0))]
+        );
+        assert!(values("&source=vision").is_empty());
+    }
 }
@@ -2,12 +2,9 @@ use std::sync::{Arc, Mutex};
 
 use rocket::{serde::json::Json, State};
 
-use crate::{
-    database::{structs::table_entree::TableEntree, SQLiteDatabase},
-    server::api::database::data_struct::Topic,
-};
+use crate::database::{structs::table_entree::TableEntree, SQLiteDatabase};
 
-use super::codes;
+use super::{codes, time_unit::TimeUnit};
 
 ///
 /// # Function
@@ -15,12 +12,18 @@ use super::codes;
 ///
 /// # Parameters
 /// - `topic`: A `String` that contains the topic to get from the database
+/// - `unit`: The unit of the returned timestamp. Milliseconds by default. OPTIONAL
+/// - `session`: The id of the recording session to get the entry from. The current session by default. OPTIONAL
+/// - `source`: Get the entry of this network table connection. The connection that updated the topic last by default. OPTIONAL
 /// - `database`: The database that will be used to get the data
 ///     - note that the database param is passed into the function by default
 ///
-#[get("/get-entry?<topic>")]
+#[get("/get-entry?<topic>&<unit>&<session>&<source>")]
 pub fn get_entry(
     topic: String,
+    unit: Option<TimeUnit>,
+    session: Option<i64>,
+    source: Option<String>,
     database: &State<Arc<Mutex<SQLiteDatabase>>>,
 ) -> Json<Result<Option<TableEntree>, codes::Error>> {
     let database = database.lock();
@@ -32,22 +35,24 @@ pub fn get_entry(
     }
 
     let database = database.unwrap();
-    Json(Ok(if let Ok(topic_value) = database.get_value(&topic) {
-        Some(topic_value)
-    } else {
-        None
-    }))
+    let unit = unit.unwrap_or_default();
+    let session = session.unwrap_or(database.current_session());
+    Json(Ok(database
+        .get_session_value(session, &topic, source.as_deref())
+        .ok()
+        .map(|entree| unit.convert_entree(entree))))
 }
 #[cfg(test)]
 /// # Function
 /// This function is used to test the get_entry function
 mod tests {
-    use std::{os::unix::thread, thread::spawn};
-
     use codes::Success;
     use rocket::{http::ContentType, local::blocking::Client};
 
-    use crate::server::api::database::test_util;
+    use crate::{
+        database::structs::table_value::TableValue,
+        server::api::database::{data_struct::Topic, test_util},
+    };
 
     use super::*;
 
@@ -112,10 +117,37 @@ mod tests {
 
         let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
             "test".to_string(),
-            "test".to_string(),
+            TableValue::String("test".to_string()),
             4,
         )));
 
         assert_eq!(body, serde_json::to_string(&expected).unwrap());
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_value_microseconds() {
+        let database = Arc::new(Mutex::new(test_util::put_data_in_database(
+            test_util::get_database(2),
+            5,
+            1,
+        )));
+        let rocket = test_util::get_rocket_build(database.clone());
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let response = client
+            .get("/get-entry?topic=test&unit=us")
+            .header(ContentType::JSON)
+            .dispatch();
+
+        let body = response.into_string().unwrap();
+
+        let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
+            "test".to_string(),
+            TableValue::String("test".to_string()),
+            4000,
+        )));
+
+        assert_eq!(body, serde_json::to_string(&expected).unwrap());
+    }
 }
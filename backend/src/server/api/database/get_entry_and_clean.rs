@@ -2,12 +2,9 @@ use std::sync::{Arc, Mutex};
 
 use rocket::{serde::json::Json, State};
 
-use crate::{
-    database::{self, structs::table_entree::TableEntree, SQLiteDatabase},
-    server::api::database::data_struct::Topic,
-};
+use crate::database::{structs::table_entree::TableEntree, SQLiteDatabase};
 
-use super::codes;
+use super::{codes, time_unit::TimeUnit};
 
 ///
 /// # Function
@@ -15,14 +12,16 @@ use super::codes;
 ///
 /// # Parameters
 /// - `topic`: A `String` that contains the topic to get from the database
-/// - `time_since_last_update`: A `u32` that contains the time since the last update. OPTIONAL
+/// - `time_since_last_update`: A `u64` that contains the time since the last update. OPTIONAL
+/// - `unit`: The unit of `time_since_last_update` and of the returned timestamp. Milliseconds by default. OPTIONAL
 /// - `database`: The database that will be used to get the data
 ///     - note that the database param is passed into the function by default
 ///
-#[get("/get-entry-and-clean?<topic>&<time_since_last_update>")]
+#[get("/get-entry-and-clean?<topic>&<time_since_last_update>&<unit>")]
 pub fn get_entry_and_clean(
     topic: String,
-    time_since_last_update: Option<u32>,
+    time_since_last_update: Option<u64>,
+    unit: Option<TimeUnit>,
     database: &State<Arc<Mutex<SQLiteDatabase>>>,
 ) -> Json<Result<Option<TableEntree>, codes::Error>> {
     let database = database.lock();
@@ -34,29 +33,29 @@ pub fn get_entry_and_clean(
     }
 
     let database = database.unwrap();
+    let unit = unit.unwrap_or_default();
     let topic_value = database.get_value(&topic);
 
-    if time_since_last_update.is_some() {
-        let _ = database.clean_database_time(time_since_last_update.unwrap());
+    if let Some(time_since_last_update) = time_since_last_update {
+        let _ = database.clean_database_time(unit.to_micros(time_since_last_update));
     } else {
         let _ = database.clean_database();
     }
 
-    Json(Ok(if let Ok(topic_value) = topic_value {
-        Some(topic_value)
-    } else {
-        None
-    }))
+    Json(Ok(topic_value
+        .ok()
+        .map(|entree| unit.convert_entree(entree))))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{os::unix::thread, thread::spawn};
-
     use codes::Success;
     use rocket::{http::ContentType, local::blocking::Client};
 
-    use crate::server::api::database::test_util;
+    use crate::{
+        database::structs::table_value::TableValue,
+        server::api::database::{data_struct::Topic, test_util},
+    };
 
     use super::*;
 
@@ -121,7 +120,7 @@ mod tests {
 
         let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
             "test".to_string(),
-            "test".to_string(),
+            TableValue::String("test".to_string()),
             4,
         )));
 
@@ -155,11 +154,39 @@ mod tests {
 
         let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
             "test".to_string(),
-            "test".to_string(),
+            TableValue::String("test".to_string()),
             4,
         )));
 
         assert_eq!(body, serde_json::to_string(&expected).unwrap());
         assert_eq!(database.lock().unwrap().length().unwrap(), 1);
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn cleaning_test_microseconds() {
+        let database = Arc::new(Mutex::new(test_util::put_data_in_database(
+            test_util::get_database(1),
+            5,
+            1,
+        )));
+        let rocket = test_util::get_rocket_build(database.clone());
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let response = client
+            .get("/get-entry-and-clean?topic=test&time_since_last_update=1500&unit=us")
+            .header(ContentType::JSON)
+            .dispatch();
+
+        let body = response.into_string().unwrap();
+
+        let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
+            "test".to_string(),
+            TableValue::String("test".to_string()),
+            4000,
+        )));
+
+        assert_eq!(body, serde_json::to_string(&expected).unwrap());
+        assert_eq!(database.lock().unwrap().length().unwrap(), 2);
+    }
 }
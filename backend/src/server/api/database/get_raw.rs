@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rocket::{
+    http::{ContentType, Header, Status},
+    serde::json::Json,
+    State,
+};
+
+use crate::database::{structs::table_value::TableValue, SQLiteDatabase};
+
+use super::{codes, time_unit::TimeUnit};
+
+///
+/// # Function
+/// How `/get-raw` sends the bytes back.
+///
+/// # Usage
+/// `?format=bytes` (default) for the bytes themselves or `?format=base64` for JSON with the bytes in base64
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default, FromFormField)]
+pub enum RawFormat {
+    #[default]
+    #[field(value = "bytes")]
+    Bytes,
+    #[field(value = "base64")]
+    Base64,
+}
+
+///
+/// # Function
+/// A raw value with its bytes in base64, what `/get-raw?format=base64` sends back.
+///
+/// # Fields
+/// - `topic`: The topic
+/// - `type`: The NT4 type string (e.g. `raw` or `struct:Pose2d`)
+/// - `timestamp`: The time of the entry, in the unit of the request
+/// - `size`: The amount of bytes
+/// - `value`: The bytes in (standard, padded) base64
+///
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RawValue {
+    pub topic: String,
+    #[serde(rename = "type")]
+    pub value_type: String,
+    pub timestamp: u64,
+    pub size: usize,
+    pub value: String,
+}
+
+/// What `/get-raw` sends back: the bytes as a file, a 404 or JSON (base64 and errors)
+#[derive(Responder)]
+pub enum RawResponse {
+    Bytes(Vec<u8>, ContentType, Header<'static>),
+    NotFound(Status),
+    Json(Json<Result<Option<RawValue>, codes::Error>>),
+}
+
+///
+/// # Function
+/// Downloads the bytes of a raw (binary) value, e.g. a PhotonVision pipeline result. The value is the one the topic had at
+/// `timestamp`: the entry at that time or the newest one before it. This is one of the api endpoints that you can call from the frontend.
+///
+/// # Parameters
+/// - `topic`: The topic to get the value of
+/// - `timestamp`: The time of the value (robot time, like the timestamps `/get-entries` sends back)
+/// - `unit`: The unit of `timestamp`. Milliseconds by default. OPTIONAL
+/// - `session`: The id of the recording session to get the value from. The current session by default. OPTIONAL
+/// - `source`: The network table connection whose clock `timestamp` is in. The connection that updated the topic last by default. OPTIONAL
+/// - `format`: `bytes` (default) or `base64`. OPTIONAL
+/// - `database`: The database that will be used to get the data
+///     - note that the database param is passed into the function by default
+///
+/// # Returns
+/// - `bytes`: The bytes as `application/octet-stream` (a file download), or 404 if there is no binary value at that time
+/// - `base64`: A `RawValue`, or `null` if there is no binary value at that time
+///
+#[get("/get-raw?<topic>&<timestamp>&<unit>&<session>&<source>&<format>")]
+#[allow(clippy::too_many_arguments)] // every query parameter is an argument
+pub fn get_raw(
+    topic: String,
+    timestamp: u64,
+    unit: Option<TimeUnit>,
+    session: Option<i64>,
+    source: Option<String>,
+    format: Option<RawFormat>,
+    database: &State<Arc<Mutex<SQLiteDatabase>>>,
+) -> RawResponse {
+    let database = database.lock();
+
+    if database.is_err() {
+        return RawResponse::Json(Json(Err(codes::Error::new(
+            &codes::Error::DatabasePoisonedError(-1),
+        ))));
+    }
+
+    let database = database.unwrap();
+    let unit = unit.unwrap_or_default();
+    // every entry inside the unit counts (e.g. the whole millisecond)
+    let until = unit
+        .to_micros(timestamp.saturating_add(1))
+        .saturating_sub(1);
+    let entree = database
+        .get_value_at(
+            session.unwrap_or(database.current_session()),
+            &topic,
+            source.as_deref(),
+            until,
+        )
+        .ok()
+        .flatten();
+    let raw = entree.and_then(|entree| match entree.value {
+        TableValue::Raw(bytes) => Some((
+            entree.value_type,
+            unit.micros_in_unit(entree.timestamp),
+            bytes,
+        )),
+        _ => None,
+    });
+
+    match (format.unwrap_or_default(), raw) {
+        (RawFormat::Bytes, Some((_, timestamp, bytes))) => RawResponse::Bytes(
+            bytes,
+            ContentType::Binary,
+            Header::new(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}-{}.bin\"",
+                    file_name(&topic),
+                    timestamp
+                ),
+            ),
+        ),
+        (RawFormat::Bytes, None) => RawResponse::NotFound(Status::NotFound),
+        (RawFormat::Base64, raw) => {
+            RawResponse::Json(Json(Ok(raw.map(|(value_type, timestamp, bytes)| {
+                RawValue {
+                    topic,
+                    value_type,
+                    timestamp,
+                    size: bytes.len(),
+                    value: STANDARD.encode(bytes),
+                }
+            }))))
+        }
+    }
+}
+
+/// The topic without the characters that can not be in a file name (`/photonvision/cam/rawBytes` -> `photonvision_cam_rawBytes`)
+fn file_name(topic: &str) -> String {
+    topic
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{database::structs::table_entree::TableEntree, server::api::database::test_util};
+
+    use super::*;
+
+    fn get_client() -> Client {
+        let mut database = test_util::get_database(2);
+        database
+            .add_values(&[
+                TableEntree::with_type(
+                    "/photonvision/cam/rawBytes".to_string(),
+                    "raw".to_string(),
+                    TableValue::Raw(vec![0x00, 0xff, 0x10]),
+                    1500,
+                ),
+                TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(1.0), 1500),
+                TableEntree::with_type(
+                    "/photonvision/cam/broken".to_string(),
+                    "raw".to_string(),
+                    TableValue::String("abc".to_string()),
+                    1500,
+                ),
+            ])
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        Client::tracked(rocket).expect("valid rocket instance")
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_download_bytes() {
+        let client = get_client();
+
+        let response = client
+            .get("/get-raw?topic=/photonvision/cam/rawBytes&timestamp=1&unit=ms")
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::Binary));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"photonvision_cam_rawBytes-1.bin\"")
+        );
+        assert_eq!(response.into_bytes().unwrap(), vec![0x00, 0xff, 0x10]);
+
+        // before the first value and not a raw topic
+        let response = client
+            .get("/get-raw?topic=/photonvision/cam/rawBytes&timestamp=1000&unit=us")
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        let response = client
+            .get("/get-raw?topic=/Drive/Speed&timestamp=2000&unit=us")
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        // a payload that did not fit the raw type is only a string
+        let response = client
+            .get("/get-raw?topic=/photonvision/cam/broken&timestamp=2000&unit=us")
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_download_base64() {
+        let client = get_client();
+
+        let response = client
+            .get("/get-raw?topic=/photonvision/cam/rawBytes&timestamp=2000&format=base64&unit=us")
+            .dispatch();
+        let body: Result<Option<RawValue>, codes::Error> =
+            serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            body,
+            Ok(Some(RawValue {
+                topic: "/photonvision/cam/rawBytes".to_string(),
+                value_type: "raw".to_string(),
+                timestamp: 1500,
+                size: 3,
+                value: "AP8Q".to_string(),
+            }))
+        );
+
+        let response = client
+            .get("/get-raw?topic=/Drive/Speed&timestamp=2000&format=base64&unit=us")
+            .dispatch();
+        assert_eq!(response.into_string().unwrap(), r#"{"Ok":null}"#);
+    }
+}
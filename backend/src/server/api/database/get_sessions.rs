@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+
+use rocket::{serde::json::Json, State};
+
+use crate::database::{structs::session::Session, SQLiteDatabase};
+
+use super::codes;
+
+///
+/// # Function
+/// Gets all the recording sessions (newest first). A session is started every time the backend starts and every time the network table (re)connects.
+/// The ids can be passed to `/get-entries` and `/get-entry` to look at the data of older sessions.
+///
+/// # Parameters
+/// - `database`: The database that will be used to get the sessions
+///     - note that the database param is passed into the function by default
+///
+#[get("/sessions")]
+pub fn get_sessions(
+    database: &State<Arc<Mutex<SQLiteDatabase>>>,
+) -> Json<Result<Vec<Session>, codes::Error>> {
+    let database = database.lock();
+
+    if database.is_err() {
+        return Json(Err(codes::Error::new(
+            &codes::Error::DatabasePoisonedError(-1),
+        )));
+    }
+
+    Json(Ok(database.unwrap().get_sessions().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use codes::Success;
+    use rocket::local::blocking::Client;
+
+    use crate::{
+        database::structs::{table_entree::TableEntree, table_value::TableValue},
+        server::api::database::test_util,
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_simulate_poison() {
+        let database = Arc::new(Mutex::new(test_util::get_database(2)));
+        let rocket = test_util::get_rocket_build(database.clone());
+
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        test_util::make_db_poisoned(database);
+
+        let response = client.get("/sessions").dispatch();
+
+        let body = response.into_string().unwrap();
+        let expected: Result<Success, codes::Error> =
+            Err(codes::Error::new(&codes::Error::DatabasePoisonedError(-1)));
+
+        let expected_error = serde_json::to_string(&expected).unwrap();
+        assert_eq!(body, expected_error);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_sessions_and_session_entries() {
+        let mut database = test_util::put_data_in_database(test_util::get_database(2), 5, 1);
+        let old_session = database.current_session();
+        database.start_session().unwrap();
+        database
+            .add_value(TableEntree::new(
+                "test".to_string(),
+                TableValue::String("new".to_string()),
+                0,
+            ))
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let sessions: Result<Vec<Session>, codes::Error> =
+            serde_json::from_str(&client.get("/sessions").dispatch().into_string().unwrap())
+                .unwrap();
+        let sessions = sessions.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].entries, 1);
+        assert_eq!(sessions[0].end_time, None);
+        assert_eq!(sessions[1].id, old_session);
+        assert_eq!(sessions[1].entries, 5);
+        assert!(sessions[1].end_time.is_some());
+
+        let body = client
+            .get(format!("/get-entry?topic=test&session={}", old_session))
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let expected: Result<Option<TableEntree>, codes::Error> = Ok(Some(TableEntree::new(
+            "test".to_string(),
+            TableValue::String("test".to_string()),
+            4,
+        )));
+        assert_eq!(body, serde_json::to_string(&expected).unwrap());
+    }
+}
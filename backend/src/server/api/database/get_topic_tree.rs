@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+
+use rocket::{serde::json::Json, State};
+
+use crate::database::{
+    structs::topic_tree::{path_segments, TopicNode, TopicSummary},
+    SQLiteDatabase,
+};
+
+use super::{codes, time_unit::TimeUnit};
+
+///
+/// # Function
+/// Gets the stored topics as a tree of tables split on `/`, with the newest value, type and update rate of every topic.
+/// The frontend can use it to browse the topics without knowing the exact topic strings. For big robots it can be loaded
+/// one level at a time with `depth=1`, starting at `/` and then asking for the `path` of the node that is opened.
+///
+/// # Parameters
+/// - `path`: The node to get (e.g. `/SmartDashboard/Drive`). The root (`/`) by default. OPTIONAL
+/// - `depth`: How many levels of children to get below `path`. Everything by default. OPTIONAL
+/// - `unit`: The unit of the returned timestamps. Milliseconds by default. OPTIONAL
+/// - `session`: The id of the recording session to get the topics of. The current session by default. OPTIONAL
+/// - `source`: Only get the topics of this network table connection (e.g. `sim`). Every connection by default. OPTIONAL
+/// - `database`: The database that will be used to get the topics
+///     - note that the database param is passed into the function by default
+///
+#[get("/topic-tree?<path>&<depth>&<unit>&<session>&<source>")]
+pub fn get_topic_tree(
+    path: Option<String>,
+    depth: Option<u32>,
+    unit: Option<TimeUnit>,
+    session: Option<i64>,
+    source: Option<String>,
+    database: &State<Arc<Mutex<SQLiteDatabase>>>,
+) -> Json<Result<TopicNode, codes::Error>> {
+    let database = database.lock();
+
+    if database.is_err() {
+        return Json(Err(codes::Error::new(
+            &codes::Error::DatabasePoisonedError(-1),
+        )));
+    }
+
+    let database = database.unwrap();
+    let path = path.unwrap_or_default();
+    let unit = unit.unwrap_or_default();
+    let session = session.unwrap_or(database.current_session());
+    let summaries = database
+        .get_topic_summaries(session, &path_segments(&path).join("/"), source.as_deref())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|summary| TopicSummary {
+            latest: unit.convert_entree(summary.latest),
+            update_rate: summary.update_rate,
+        })
+        .collect();
+
+    Json(Ok(TopicNode::build(summaries, &path, depth)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{
+        database::structs::{table_entree::TableEntree, table_value::TableValue},
+        server::api::database::test_util,
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_topic_tree() {
+        let mut database = test_util::get_database(2);
+        database
+            .add_values(&[
+                TableEntree::new(
+                    "/SmartDashboard/Drive/Speed".to_string(),
+                    TableValue::Double(1.0),
+                    1000,
+                ),
+                TableEntree::new(
+                    "/SmartDashboard/Drive/Speed".to_string(),
+                    TableValue::Double(2.0),
+                    2000,
+                ),
+                TableEntree::new(
+                    "/SmartDashboard/Arm/Angle".to_string(),
+                    TableValue::Double(0.0),
+                    2000,
+                ),
+                TableEntree::new(
+                    "/FMSInfo/IsRedAlliance".to_string(),
+                    TableValue::Boolean(true),
+                    2000,
+                ),
+            ])
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let root: Result<TopicNode, codes::Error> = serde_json::from_str(
+            &client
+                .get("/topic-tree?depth=1")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+        let root = root.unwrap();
+        assert_eq!(root.topic_count, 3);
+        assert_eq!(root.children.len(), 2);
+        assert!(root.children[1].children.is_empty());
+
+        let drive: Result<TopicNode, codes::Error> = serde_json::from_str(
+            &client
+                .get("/topic-tree?path=/SmartDashboard/Drive&unit=ms")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+        let latest = drive.unwrap().children[0].latest.clone().unwrap();
+        assert_eq!(latest.topic, "/SmartDashboard/Drive/Speed");
+        assert_eq!(latest.value, TableValue::Double(2.0));
+        assert_eq!(latest.timestamp, 2);
+    }
+}
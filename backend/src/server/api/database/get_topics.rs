@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+use rocket::{serde::json::Json, State};
+
+use crate::database::{structs::topic_info::TopicInfo, SQLiteDatabase};
+
+use super::codes;
+
+///
+/// # Function
+/// Gets the topic catalog: every topic that was announced or recorded, with its type, NT4 properties, first/last seen times
+/// and the amount of stored rows. Sorted by name.
+///
+/// # Parameters
+/// - `prefix`: Only get the topics that start with this. Defaults to all topics
+/// - `database`: The database that will be used to get the topics
+///     - note that the database param is passed into the function by default
+///
+#[get("/topics?<prefix>")]
+pub fn get_topics(
+    prefix: Option<String>,
+    database: &State<Arc<Mutex<SQLiteDatabase>>>,
+) -> Json<Result<Vec<TopicInfo>, codes::Error>> {
+    let database = database.lock();
+
+    if database.is_err() {
+        return Json(Err(codes::Error::new(
+            &codes::Error::DatabasePoisonedError(-1),
+        )));
+    }
+
+    Json(Ok(database
+        .unwrap()
+        .get_topics(&prefix.unwrap_or_default())
+        .unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{
+        database::structs::{table_entree::TableEntree, table_value::TableValue},
+        server::api::database::test_util,
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_topics() {
+        let mut database = test_util::get_database(2);
+        database.unannounce_topic(None, None).unwrap();
+        database.clear_database().unwrap();
+        database
+            .add_values(&[
+                TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(1.0), 0),
+                TableEntree::new("/Drive/Speed".to_string(), TableValue::Double(2.0), 1),
+                TableEntree::new("/Arm/Angle".to_string(), TableValue::Double(0.0), 1),
+            ])
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let topics: Result<Vec<TopicInfo>, codes::Error> = serde_json::from_str(
+            &client
+                .get("/topics?prefix=/Drive/")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+        let topics = topics.unwrap();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].name, "/Drive/Speed");
+        assert_eq!(topics[0].value_type, "double");
+        assert_eq!(topics[0].rows, 2);
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+
+use rocket::{serde::json::Json, State};
+
+use crate::database::{structs::write_record::WriteRecord, SQLiteDatabase};
+
+use super::codes;
+
+///
+/// # Function
+/// Gets the audit log of the values that were written to the network table from the dashboard (newest first).
+///
+/// # Parameters
+/// - `amount`: The maximum amount of writes to get. Defaults to 100
+/// - `database`: The database that will be used to get the writes
+///     - note that the database param is passed into the function by default
+///
+#[get("/writes?<amount>")]
+pub fn get_writes(
+    amount: Option<u32>,
+    database: &State<Arc<Mutex<SQLiteDatabase>>>,
+) -> Json<Result<Vec<WriteRecord>, codes::Error>> {
+    let database = database.lock();
+
+    if database.is_err() {
+        return Json(Err(codes::Error::new(
+            &codes::Error::DatabasePoisonedError(-1),
+        )));
+    }
+
+    match database.unwrap().get_writes(amount.unwrap_or(100)) {
+        Ok(writes) => Json(Ok(writes)),
+        Err(_) => Json(Err(codes::Error::new(&codes::Error::DatabaseQueryError(
+            -1,
+        )))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{database::structs::table_value::TableValue, server::api::database::test_util};
+
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_writes() {
+        let database = test_util::get_database(2);
+        for i in 0..3 {
+            database
+                .add_write(&WriteRecord {
+                    time: i,
+                    topic: "/Tuning/kP".to_string(),
+                    value_type: "double".to_string(),
+                    value: TableValue::Double(i as f64),
+                    origin: "http".to_string(),
+                    client: None,
+                    error: None,
+                })
+                .unwrap();
+        }
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let writes: Result<Vec<WriteRecord>, codes::Error> = serde_json::from_str(
+            &client
+                .get("/writes?amount=2")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+        let writes = writes.unwrap();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].value, TableValue::Double(2.0));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_get_writes_unreadable() {
+        let database = test_util::get_database(2);
+        // a boolean can not be read back from a real
+        database
+            .add_write(&WriteRecord {
+                time: 0,
+                topic: "/Tuning/Enabled".to_string(),
+                value_type: "boolean".to_string(),
+                value: TableValue::Double(0.5),
+                origin: "http".to_string(),
+                client: None,
+                error: None,
+            })
+            .unwrap();
+
+        let rocket = test_util::get_rocket_build(Arc::new(Mutex::new(database)));
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let writes: Result<Vec<WriteRecord>, codes::Error> =
+            serde_json::from_str(&client.get("/writes").dispatch().into_string().unwrap()).unwrap();
+        assert_eq!(writes, Err(codes::Error::DatabaseQueryError(2)));
+    }
+}
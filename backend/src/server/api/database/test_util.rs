@@ -8,11 +8,16 @@ use std::{
 use colored::Colorize;
 use rocket::{Build, Rocket};
 
-use crate::database::{structs::table_entree::TableEntree, SQLiteDatabase};
+use crate::database::{
+    structs::{table_entree::TableEntree, table_value::TableValue},
+    SQLiteDatabase,
+};
 
 use super::{
     clean_whole_db::clean_whole_database, clear_database::clear_database, data_struct::Topic,
     get_entries::get_entries, get_entry::get_entry, get_entry_and_clean::get_entry_and_clean,
+    get_raw::get_raw, get_sessions::get_sessions, get_topic_tree::get_topic_tree,
+    get_topics::get_topics, get_writes::get_writes,
 };
 
 ///
@@ -22,7 +27,7 @@ use super::{
 /// # Parameters
 /// - `min_time_between_cleans`: The minimum time between database cleans.
 ///
-pub fn get_database(min_time_between_cleans: u32) -> SQLiteDatabase {
+pub fn get_database(min_time_between_cleans: u64) -> SQLiteDatabase {
     if !std::path::Path::new("test.db").exists() {
         let _ = File::create("test.db"); // create an empty file
     }
@@ -60,18 +65,18 @@ pub fn to_get_request(topic: Topic, initial_string: &str) -> String {
 /// # Parameters
 /// - `database`: The database that will be used to store the data. It is mutable and it is taken ownership of.
 /// - `data_amt`: The amount of data that will be put in the database.
-/// - `time_step`: The time step that will be used to put the data in the database.
+/// - `time_step`: The time step that will be used to put the data in the database, in milliseconds (the default unit of the API).
 ///
 pub fn put_data_in_database(
     mut database: SQLiteDatabase,
-    data_amt: u32,
-    time_step: u32,
+    data_amt: u64,
+    time_step: u64,
 ) -> SQLiteDatabase {
     for i in 0..data_amt {
         let _ = database.add_value(TableEntree::new(
             "test".to_string(),
-            "test".to_string(),
-            i * time_step,
+            TableValue::String("test".to_string()),
+            i * time_step * 1000,
         ));
     }
 
@@ -95,8 +100,13 @@ pub fn get_rocket_build(db: Arc<Mutex<SQLiteDatabase>>) -> Rocket<Build> {
             get_entry_and_clean,
             get_entries,
             get_entry,
+            get_raw,
             clear_database,
-            clean_whole_database
+            clean_whole_database,
+            get_sessions,
+            get_topic_tree,
+            get_topics,
+            get_writes
         ],
     )
 }
@@ -115,7 +125,7 @@ pub fn get_rocket_build(db: Arc<Mutex<SQLiteDatabase>>) -> Rocket<Build> {
 ///
 pub fn make_db_poisoned(db: Arc<Mutex<SQLiteDatabase>>) {
     let handle = spawn(move || {
-        let a = db.lock();
+        let _guard = db.lock();
         panic!(
             "{}",
             "This is OK! I'm going to panic. (intended panic)".green()
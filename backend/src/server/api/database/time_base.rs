@@ -0,0 +1,16 @@
+///
+/// # Function
+/// The clock that the API uses for `time_since_last_update`. The robot time starts over every time the robot reboots, the
+/// wall clock time (when the backend got the entry) keeps going so entries from different sessions line up.
+///
+/// # Usage
+/// `?time_base=robot` (default) or `?time_base=wall`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default, FromFormField)]
+pub enum TimeBase {
+    #[default]
+    #[field(value = "robot")]
+    Robot,
+    #[field(value = "wall")]
+    Wall,
+}
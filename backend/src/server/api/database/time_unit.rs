@@ -0,0 +1,53 @@
+use crate::database::structs::table_entree::TableEntree;
+
+///
+/// # Function
+/// The unit that the API uses for timestamps. Entries are always stored in microseconds, this only changes what is sent back
+/// to the frontend and how the `time_since_last_update` parameter is read. Milliseconds are the default, so clients that were
+/// made before the timestamps were stored in microseconds keep working.
+///
+/// # Usage
+/// `?unit=ms` (default) or `?unit=us`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default, FromFormField)]
+pub enum TimeUnit {
+    #[field(value = "us")]
+    Microseconds,
+    #[default]
+    #[field(value = "ms")]
+    Milliseconds,
+}
+
+impl TimeUnit {
+    ///
+    /// # Function
+    /// Converts a time in this unit to microseconds (the unit that the database uses).
+    ///
+    pub fn to_micros(self, time: u64) -> u64 {
+        match self {
+            TimeUnit::Microseconds => time,
+            TimeUnit::Milliseconds => time.saturating_mul(1000),
+        }
+    }
+
+    ///
+    /// # Function
+    /// Converts a time in microseconds (the unit that the database uses) to this unit.
+    ///
+    pub fn micros_in_unit(self, time: u64) -> u64 {
+        match self {
+            TimeUnit::Microseconds => time,
+            TimeUnit::Milliseconds => time / 1000,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Changes the timestamp and the wall clock time of an entry (which are in microseconds) to this unit.
+    ///
+    pub fn convert_entree(self, mut entree: TableEntree) -> TableEntree {
+        entree.timestamp = self.micros_in_unit(entree.timestamp);
+        entree.wall_time = entree.wall_time.map(|time| self.micros_in_unit(time));
+        entree
+    }
+}
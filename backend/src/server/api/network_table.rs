@@ -0,0 +1,3 @@
+// This file is just to make everything look pretty
+
+pub mod write;
@@ -0,0 +1,77 @@
+use std::net::SocketAddr;
+
+use rocket::{serde::json::Json, State};
+
+use crate::network_table_bridge::writer::{NetworkTableWriter, WriteError, WriteRequest};
+
+///
+/// # Function
+/// Writes a value to the network table (e.g. to tune a PID gain or toggle a debug flag). Only topics matching
+/// `NETWORK_TABLE_WRITABLE_TOPICS` can be written. Every write, also the refused ones, is added to the audit log (`/writes`).
+///
+/// # Parameters
+/// - `request`: The topic, NT4 type and value to write
+/// - `writer`: The writer that publishes the value through the network table client
+///     - note that the writer param is passed into the function by default
+/// - `remote`: The address of the client, stored in the audit log
+///
+#[post("/network-table/write", format = "json", data = "<request>")]
+pub async fn write_value(
+    request: Json<WriteRequest>,
+    writer: &State<NetworkTableWriter>,
+    remote: Option<SocketAddr>,
+) -> Json<Result<(), WriteError>> {
+    Json(
+        writer
+            .write(
+                request.into_inner(),
+                "http",
+                remote.map(|remote| remote.to_string()),
+            )
+            .await,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use rocket::{http::ContentType, local::blocking::Client};
+
+    use crate::{
+        database::{structs::table_value::TableValue, SQLiteDatabase},
+        network_table_bridge::topic_filter::TopicFilter,
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_write_value() {
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        database.clear_database().unwrap();
+        let database = Arc::new(Mutex::new(database));
+        let writer =
+            NetworkTableWriter::new(TopicFilter::from_lists("/Tuning/", ""), database.clone());
+
+        let rocket = rocket::build()
+            .manage(writer)
+            .mount("/", routes![write_value]);
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let body = client
+            .post("/network-table/write")
+            .header(ContentType::JSON)
+            .body(r#"{"topic":"/Tuning/kP","type":"double","value":0.05}"#)
+            .dispatch()
+            .into_string()
+            .unwrap();
+        let expected: Result<(), WriteError> = Err(WriteError::NotConnected);
+        assert_eq!(body, serde_json::to_string(&expected).unwrap());
+
+        let writes = database.lock().unwrap().get_writes(1).unwrap();
+        assert_eq!(writes[0].topic, "/Tuning/kP");
+        assert_eq!(writes[0].value, TableValue::Double(0.05));
+        assert_eq!(writes[0].origin, "http");
+    }
+}
@@ -0,0 +1,4 @@
+// This file is just to make everything look pretty
+
+pub mod ingest;
+pub mod network_table;
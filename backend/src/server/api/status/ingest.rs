@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use rocket::{serde::json::Json, State};
+
+use crate::ingest::{IngestCounters, IngestStats};
+
+///
+/// # Function
+/// Gets the counters of the ingestion pipeline. This is how you can tell if the backend is keeping up with the robot.
+///
+/// # Parameters
+/// - `counters`: The counters of the ingestion pipeline
+///     - note that the counters param is passed into the function by default
+///
+#[get("/status/ingest")]
+pub fn get_ingest_status(counters: &State<Arc<IngestCounters>>) -> Json<IngestStats> {
+    Json(counters.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{
+        database::structs::{table_entree::TableEntree, table_value::TableValue},
+        ingest::{Ingest, IngestConfig},
+        live::LiveFeed,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_get_ingest_status() {
+        let (ingest, _writer) = Ingest::new(
+            IngestConfig {
+                channel_capacity: 1,
+                ..Default::default()
+            },
+            LiveFeed::default(),
+        );
+        ingest.push(TableEntree::new("test".to_string(), TableValue::Int(1), 0));
+        ingest.push(TableEntree::new("test".to_string(), TableValue::Int(1), 1));
+
+        let rocket = rocket::build()
+            .manage(ingest.counters())
+            .mount("/", routes![get_ingest_status]);
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let body = client
+            .get("/status/ingest")
+            .dispatch()
+            .into_string()
+            .unwrap();
+        assert_eq!(
+            body,
+            r#"{"queued":1,"flushed":0,"dropped":1,"deduplicated":0}"#
+        );
+    }
+}
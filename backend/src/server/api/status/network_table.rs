@@ -0,0 +1,122 @@
+use rocket::{serde::json::Json, State};
+
+use crate::network_table_bridge::connection_status::{
+    ConnectionStatus, ConnectionTracker, ConnectionTrackers,
+};
+
+///
+/// # Function
+/// Gets the state of the connection to the network table (robot link health). The same status is pushed to the live WebSocket clients when it changes.
+///
+/// # Parameters
+/// - `connection`: The tracker that the network table bridge updates
+///     - note that the connection param is passed into the function by default
+///
+#[get("/status/network-table")]
+pub fn get_network_table_status(connection: &State<ConnectionTracker>) -> Json<ConnectionStatus> {
+    Json(connection.status())
+}
+
+///
+/// # Function
+/// Gets the state of every network table connection (see `NETWORK_TABLE_CONNECTIONS`), the main connection first.
+///
+/// # Parameters
+/// - `connections`: The trackers of every connection
+///     - note that the connections param is passed into the function by default
+///
+#[get("/status/network-tables")]
+pub fn get_network_tables_status(
+    connections: &State<ConnectionTrackers>,
+) -> Json<Vec<ConnectionStatus>> {
+    Json(
+        connections
+            .0
+            .iter()
+            .map(ConnectionTracker::status)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::local::blocking::Client;
+
+    use crate::{live::LiveFeed, network_table_bridge::connection_status::ConnectionState};
+
+    use super::*;
+
+    #[test]
+    fn test_get_network_table_status() {
+        let connection = ConnectionTracker::new(
+            "robot".to_string(),
+            "127.0.0.1:5810".to_string(),
+            LiveFeed::default(),
+        );
+        connection.connecting("127.0.0.1:5810".to_string());
+        connection.failed("Timed out connecting to server".to_string());
+
+        let rocket = rocket::build()
+            .manage(connection)
+            .mount("/", routes![get_network_table_status]);
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let status: ConnectionStatus = serde_json::from_str(
+            &client
+                .get("/status/network-table")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(status.state, ConnectionState::Disconnected);
+        assert_eq!(status.address, "127.0.0.1:5810");
+        assert_eq!(status.reconnect_attempts, 1);
+        assert_eq!(
+            status.last_error,
+            Some("Timed out connecting to server".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_network_tables_status() {
+        let robot = ConnectionTracker::new(
+            "robot".to_string(),
+            "10.0.0.2:5810".to_string(),
+            LiveFeed::default(),
+        );
+        let sim = ConnectionTracker::new(
+            "sim".to_string(),
+            "127.0.0.1:5810".to_string(),
+            LiveFeed::default(),
+        );
+        sim.connected();
+
+        let rocket = rocket::build()
+            .manage(ConnectionTrackers(vec![robot, sim]))
+            .mount("/", routes![get_network_tables_status]);
+        let client = Client::tracked(rocket).expect("valid rocket instance");
+
+        let statuses: Vec<ConnectionStatus> = serde_json::from_str(
+            &client
+                .get("/status/network-tables")
+                .dispatch()
+                .into_string()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let states: Vec<(&str, ConnectionState)> = statuses
+            .iter()
+            .map(|status| (status.source.as_str(), status.state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("robot", ConnectionState::Disconnected),
+                ("sim", ConnectionState::Connected)
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use futures::future::LocalBoxFuture;
+use tokio::task::spawn_local;
+
+use crate::ingest::Ingest;
+
+pub mod generator;
+pub mod nt4_server;
+
+///
+/// # Function
+/// Something that produces entries for the database, like the network table. A source pushes every `TableEntree` it gets
+/// into the ingestion pipeline, which streams it live and writes it to the database. Sources can start and end recording
+/// sessions through the pipeline too.
+///
+/// # Adding a source
+/// Implement this trait and add the source to the list that is passed to `run_all`. Sources run on the local set, so they
+/// do not have to be `Send`.
+///
+pub trait Source {
+    ///
+    /// # Function
+    /// A name for the logs.
+    ///
+    fn name(&self) -> String;
+
+    ///
+    /// # Function
+    /// Runs the source. The returned future should only finish when the source has nothing more to give.
+    ///
+    /// # Parameters
+    /// - `ingest`: The ingestion pipeline that the entries are pushed into
+    ///
+    fn run(self: Box<Self>, ingest: Ingest) -> LocalBoxFuture<'static, ()>;
+}
+
+///
+/// # Function
+/// Runs every source at the same time. Has to be called inside a `tokio::task::LocalSet`.
+///
+/// # Returns
+/// Once every source has finished
+///
+pub async fn run_all(sources: Vec<Box<dyn Source>>, ingest: Ingest) {
+    let tasks: Vec<_> = sources
+        .into_iter()
+        .map(|source| {
+            let name = source.name();
+            println!("Starting source {}", name);
+            let task = spawn_local(source.run(ingest.clone()));
+            (name, task)
+        })
+        .collect();
+    drop(ingest); // only the sources keep the pipeline open
+
+    for (name, task) in tasks {
+        let _ = task.await;
+        println!("Source {} finished", name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        database::structs::{table_entree::TableEntree, table_value::TableValue},
+        ingest::IngestConfig,
+        live::LiveFeed,
+    };
+
+    use super::*;
+
+    struct ListSource(Vec<TableEntree>);
+
+    impl Source for ListSource {
+        fn name(&self) -> String {
+            "list".to_string()
+        }
+
+        fn run(self: Box<Self>, ingest: Ingest) -> LocalBoxFuture<'static, ()> {
+            Box::pin(async move {
+                for entree in self.0 {
+                    ingest.push(entree);
+                    tokio::task::yield_now().await;
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_all() {
+        let (ingest, _writer) = Ingest::new(IngestConfig::default(), LiveFeed::default());
+        let counters = ingest.counters();
+        let entree = |topic: &str| TableEntree::new(topic.to_string(), TableValue::Int(1), 0);
+        let sources: Vec<Box<dyn Source>> = vec![
+            Box::new(ListSource(vec![entree("/a"), entree("/a")])),
+            Box::new(ListSource(vec![entree("/b")])),
+        ];
+
+        tokio::task::LocalSet::new()
+            .run_until(run_all(sources, ingest))
+            .await;
+
+        assert_eq!(counters.stats().queued, 3);
+    }
+}
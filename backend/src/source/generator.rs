@@ -0,0 +1,328 @@
+use std::{
+    f64::consts::TAU,
+    time::{Duration, Instant},
+};
+
+use futures::future::LocalBoxFuture;
+
+use super::Source;
+use crate::{
+    database::structs::{table_entree::TableEntree, table_value::TableValue},
+    ingest::Ingest,
+};
+
+///
+/// # Function
+/// Settings for the synthetic data generator.
+///
+/// # Fields
+/// - `rate`: How often the fast topics (motor outputs and the pose) are published (per second)
+/// - `slow_rate`: How often the slow topics (battery voltage and the enabled state) are published (per second)
+/// - `seed`: The seed of the noise, the same seed always gives the same data
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorConfig {
+    pub rate: f64,
+    pub slow_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            rate: 50.0,
+            slow_rate: 5.0,
+            seed: 1,
+        }
+    }
+}
+
+/// The `source` the entries of the generator are tagged with, so its clock is kept apart from the clocks of the other sources
+pub const SOURCE: &str = "generator";
+
+/// How long one enable/disable cycle of the fake robot is (microseconds)
+const CYCLE: u64 = 20_000_000;
+/// How long the fake robot is enabled in every cycle (microseconds)
+const ENABLED_TIME: u64 = 15_000_000;
+/// The speed of the drivetrain at full output (meters per second)
+const MAX_SPEED: f64 = 4.0;
+/// The distance between the left and right wheels (meters)
+const TRACK_WIDTH: f64 = 0.6;
+
+///
+/// # Function
+/// A fake robot that makes realistic looking data. Timestamps are microseconds since the generator started, like the
+/// time of a robot that was just turned on.
+///
+/// # Topics
+/// - `motor_speed` (`double`): A sine wave, the topic the example page of the frontend shows
+/// - `/SmartDashboard/Drive/LeftOutput`, `/SmartDashboard/Drive/RightOutput` (`double`): Sine waves with noise
+/// - `/SmartDashboard/Arm/Output` (`double`): A step between two outputs every 2 seconds
+/// - `/SmartDashboard/Drive/Pose` (`double[]`): `[x, y, heading]` (meters and degrees) of the drivetrain driven by the outputs above
+/// - `/SmartDashboard/Battery/Voltage` (`double`): Sags with the motor outputs
+/// - `/SmartDashboard/Robot/Enabled` (`boolean`): Enabled for 15 seconds out of every 20, every output is `0` while disabled
+///
+#[derive(Debug, Clone)]
+pub struct RobotModel {
+    random_state: u64,
+    pose: [f64; 3],
+    last_fast: Option<u64>,
+}
+
+impl RobotModel {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            random_state: seed | 1, // xorshift gets stuck on 0
+            pose: [0.0; 3],
+            last_fast: None,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Gets the values of the fast topics at a time and moves the pose forward to it.
+    ///
+    pub fn fast(&mut self, time: u64) -> Vec<TableEntree> {
+        let (left, right) = self.drive_outputs(time);
+        let seconds = time as f64 / 1_000_000.0;
+
+        let dt = self
+            .last_fast
+            .map(|last| time.saturating_sub(last) as f64 / 1_000_000.0)
+            .unwrap_or(0.0);
+        self.last_fast = Some(time);
+        let speed = (left + right) / 2.0 * MAX_SPEED;
+        let turn = (right - left) * MAX_SPEED / TRACK_WIDTH;
+        self.pose[2] += turn * dt;
+        self.pose[0] += speed * self.pose[2].cos() * dt;
+        self.pose[1] += speed * self.pose[2].sin() * dt;
+
+        let arm = if !is_enabled(time) {
+            0.0
+        } else if (time / 2_000_000).is_multiple_of(2) {
+            0.6
+        } else {
+            -0.3
+        };
+        let motor_speed = if is_enabled(time) {
+            0.8 * (TAU * 0.5 * seconds).sin()
+        } else {
+            0.0
+        };
+
+        vec![
+            double("motor_speed", motor_speed, time),
+            double("/SmartDashboard/Drive/LeftOutput", left, time),
+            double("/SmartDashboard/Drive/RightOutput", right, time),
+            double("/SmartDashboard/Arm/Output", arm, time),
+            TableEntree::new(
+                "/SmartDashboard/Drive/Pose".to_string(),
+                TableValue::DoubleArray(vec![
+                    self.pose[0],
+                    self.pose[1],
+                    self.pose[2].to_degrees().rem_euclid(360.0),
+                ]),
+                time,
+            ),
+        ]
+    }
+
+    ///
+    /// # Function
+    /// Gets the values of the slow topics at a time.
+    ///
+    pub fn slow(&mut self, time: u64) -> Vec<TableEntree> {
+        let (left, right) = self.drive_outputs(time);
+        let load = (left.abs() + right.abs()) / 2.0;
+        let voltage = 12.6 - 2.5 * load + self.noise(0.05);
+
+        vec![
+            double("/SmartDashboard/Battery/Voltage", voltage, time),
+            TableEntree::new(
+                "/SmartDashboard/Robot/Enabled".to_string(),
+                TableValue::Boolean(is_enabled(time)),
+                time,
+            ),
+        ]
+    }
+
+    fn drive_outputs(&mut self, time: u64) -> (f64, f64) {
+        if !is_enabled(time) {
+            return (0.0, 0.0);
+        }
+
+        let seconds = time as f64 / 1_000_000.0;
+        let left = 0.6 * (TAU * 0.1 * seconds).sin();
+        let right = 0.6 * (TAU * 0.1 * seconds + 0.4).sin();
+        (
+            (left + self.noise(0.02)).clamp(-1.0, 1.0),
+            (right + self.noise(0.02)).clamp(-1.0, 1.0),
+        )
+    }
+
+    /// A random number between `-amplitude` and `amplitude` (xorshift64)
+    fn noise(&mut self, amplitude: f64) -> f64 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        let random = (self.random_state >> 11) as f64 / (1u64 << 53) as f64;
+        (random * 2.0 - 1.0) * amplitude
+    }
+}
+
+fn is_enabled(time: u64) -> bool {
+    time % CYCLE < ENABLED_TIME
+}
+
+fn double(topic: &str, value: f64, time: u64) -> TableEntree {
+    TableEntree::new(topic.to_string(), TableValue::Double(value), time)
+}
+
+///
+/// # Function
+/// A source that publishes the data of a `RobotModel` through the normal ingestion pipeline, so the frontend can be worked
+/// on without a robot or a simulator. It records into the current recording session (it never starts one, so it does not
+/// cut the sessions of a robot that runs next to it) and never stops.
+///
+#[derive(Debug, Clone)]
+pub struct GeneratorSource {
+    config: GeneratorConfig,
+}
+
+impl GeneratorSource {
+    pub fn new(config: GeneratorConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Source for GeneratorSource {
+    fn name(&self) -> String {
+        format!("generator ({} Hz)", self.config.rate)
+    }
+
+    fn run(self: Box<Self>, ingest: Ingest) -> LocalBoxFuture<'static, ()> {
+        Box::pin(async move {
+            let mut model = RobotModel::new(self.config.seed);
+            let mut fast = tokio::time::interval(period(self.config.rate));
+            let mut slow = tokio::time::interval(period(self.config.slow_rate));
+            let start = Instant::now();
+
+            loop {
+                let entries = tokio::select! {
+                    _ = fast.tick() => model.fast(start.elapsed().as_micros() as u64),
+                    _ = slow.tick() => model.slow(start.elapsed().as_micros() as u64),
+                };
+
+                for mut entree in entries {
+                    entree.source = Some(SOURCE.to_string());
+                    ingest.push(entree);
+                }
+            }
+        })
+    }
+}
+
+fn period(rate: f64) -> Duration {
+    Duration::from_secs_f64(1.0 / rate.clamp(0.01, 10_000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::database::SQLiteDatabase;
+
+    fn value(entries: &[TableEntree], topic: &str) -> TableValue {
+        entries
+            .iter()
+            .find(|entree| entree.topic == topic)
+            .unwrap()
+            .value
+            .clone()
+    }
+
+    #[test]
+    fn test_enabled_cycle() {
+        let mut model = RobotModel::new(1);
+
+        let enabled = model.slow(1_000_000);
+        assert_eq!(
+            value(&enabled, "/SmartDashboard/Robot/Enabled"),
+            TableValue::Boolean(true)
+        );
+
+        let disabled = model.fast(16_000_000);
+        assert_eq!(
+            value(&disabled, "/SmartDashboard/Arm/Output"),
+            TableValue::Double(0.0)
+        );
+        assert_eq!(
+            value(&model.slow(16_000_000), "/SmartDashboard/Robot/Enabled"),
+            TableValue::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn test_values_are_realistic() {
+        let mut model = RobotModel::new(7);
+        for step in 0..1000 {
+            let time = step * 20_000;
+            for entree in model.fast(time).into_iter().chain(model.slow(time)) {
+                match (entree.topic.as_str(), entree.value) {
+                    ("/SmartDashboard/Battery/Voltage", TableValue::Double(voltage)) => {
+                        assert!((9.0..=12.7).contains(&voltage))
+                    }
+                    ("/SmartDashboard/Drive/Pose", TableValue::DoubleArray(pose)) => {
+                        assert_eq!(pose.len(), 3);
+                        assert!((0.0..360.0).contains(&pose[2]));
+                    }
+                    (_, TableValue::Double(output)) => assert!((-1.0..=1.0).contains(&output)),
+                    (_, TableValue::Boolean(_)) => {}
+                    (topic, value) => panic!("unexpected {} {:?}", topic, value),
+                }
+            }
+        }
+
+        // the robot moved
+        let TableValue::DoubleArray(pose) =
+            value(&model.fast(20_000_000), "/SmartDashboard/Drive/Pose")
+        else {
+            panic!("the pose is not a double array");
+        };
+        assert!(pose[0] != 0.0 || pose[1] != 0.0);
+    }
+
+    #[test]
+    fn test_same_seed_same_data() {
+        assert_eq!(
+            RobotModel::new(3).slow(1_000),
+            RobotModel::new(3).slow(1_000)
+        );
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_pushes_through_ingest() {
+        let database = Arc::new(Mutex::new(SQLiteDatabase::new("test.db", 2).unwrap()));
+        let session = database.lock().unwrap().current_session();
+        let (ingest, writer) = Ingest::new(Default::default(), Default::default());
+        let counters = ingest.counters();
+        let source = Box::new(GeneratorSource::new(GeneratorConfig {
+            rate: 1000.0,
+            ..Default::default()
+        }));
+
+        let _ = tokio::time::timeout(Duration::from_millis(50), source.run(ingest)).await;
+        assert!(counters.stats().queued > 5);
+
+        // the source is gone, so the writer stops after writing everything
+        writer.run(database.clone()).await;
+        let database = database.lock().unwrap();
+        assert_eq!(database.current_session(), session);
+        assert_eq!(
+            database.get_value("motor_speed").unwrap().source,
+            Some(SOURCE.to_string())
+        );
+    }
+}
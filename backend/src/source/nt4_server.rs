@@ -0,0 +1,779 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use futures::{future::LocalBoxFuture, SinkExt, StreamExt};
+use network_tables::{rmpv, v4::Type, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+    task::spawn_local,
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    Message,
+};
+
+use super::Source;
+use crate::{
+    database::{
+        now_micros,
+        structs::{table_entree::TableEntree, topic_info::TopicInfo},
+    },
+    ingest::Ingest,
+};
+
+///
+/// # Function
+/// Settings for the embedded NT4 server.
+///
+/// # Fields
+/// - `name`: The name of the server. Every entry and catalog topic it records is tagged with it (the `source` column)
+/// - `port`: The port the server listens on (on every interface)
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nt4ServerConfig {
+    pub name: String,
+    pub port: u16,
+}
+
+impl Default for Nt4ServerConfig {
+    fn default() -> Self {
+        Self {
+            name: "server".to_string(),
+            port: 5810,
+        }
+    }
+}
+
+///
+/// # Function
+/// An NT4 server inside the backend, for when there is no robot to connect to (bench testing a coprocessor, demos, a
+/// simulator that is only a client). Coprocessors and sim clients connect to it like they would to a roboRIO. Every value
+/// they publish is recorded through the ingestion pipeline (like the values of the network table bridge) and sent to the
+/// clients that are subscribed to it, so the clients can also see each other. It records into the current recording session
+/// (like the generator it never starts one, so it does not cut the session of a robot that is recorded next to it).
+///
+/// # Protocol
+/// Announcements, subscriptions (prefix or exact, `topicsonly`), values, `setproperties` and time sync. Values are sent right
+/// away, `periodic` is ignored. Timestamps are the server time (microseconds since the server started), which is also the
+/// `timestamp` of the recorded entries. A topic is unannounced when its last publisher is gone, unless it is `retained` or
+/// `persistent`. Persistent topics are not saved to a file.
+///
+pub struct Nt4ServerSource {
+    config: Nt4ServerConfig,
+    listener: TcpListener,
+}
+
+impl Nt4ServerSource {
+    ///
+    /// # Function
+    /// Starts listening on the port of the config. Clients are only accepted once the source is run.
+    ///
+    /// # Returns
+    /// The error if the port can not be used (e.g. another NT server is already running on it)
+    ///
+    pub async fn bind(config: Nt4ServerConfig) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", config.port)).await?;
+        Ok(Self { config, listener })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+}
+
+impl Source for Nt4ServerSource {
+    fn name(&self) -> String {
+        match self.local_addr() {
+            Ok(address) => format!("NT4 server {} ({})", self.config.name, address),
+            Err(_) => format!("NT4 server {}", self.config.name),
+        }
+    }
+
+    fn run(self: Box<Self>, ingest: Ingest) -> LocalBoxFuture<'static, ()> {
+        Box::pin(async move {
+            let server = Nt4Server::new(Some(Recorder {
+                name: self.config.name,
+                ingest,
+            }));
+
+            while let Ok((stream, address)) = self.listener.accept().await {
+                println!("NT4 client connected from {}", address);
+                spawn_local(server.clone().handle_client(stream));
+            }
+        })
+    }
+}
+
+///
+/// # Function
+/// The NT4 server itself, without the listener: the topics, the clients and the time sync. `Nt4ServerSource` runs it with a
+/// `Recorder` and the test server of the network table bridge runs it without one. It is cheap to clone, every clone is the
+/// same server.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct Nt4Server {
+    state: Arc<Mutex<ServerState>>,
+    events: broadcast::Sender<Event>,
+    recorder: Option<Recorder>,
+}
+
+///
+/// # Function
+/// Where a server records the topics and values of its clients.
+///
+/// # Fields
+/// - `name`: The `source` of every entry and catalog topic that is recorded
+/// - `ingest`: The ingestion pipeline that everything is recorded through
+///
+#[derive(Debug, Clone)]
+pub(crate) struct Recorder {
+    pub name: String,
+    pub ingest: Ingest,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    start: Instant,
+    /// The wall clock time (UNIX microseconds) at server time `0`
+    start_wall: u64,
+    next_id: i32,
+    topics: HashMap<String, ServerTopic>,
+}
+
+#[derive(Debug, Clone)]
+struct ServerTopic {
+    id: i32,
+    r#type: String,
+    properties: serde_json::Value,
+    publishers: usize,
+    value: Option<(u64, Value)>,
+}
+
+impl ServerTopic {
+    /// Retained and persistent topics stay announced when nobody publishes them anymore
+    fn is_kept(&self) -> bool {
+        ["retained", "persistent"].iter().any(|name| {
+            self.properties
+                .get(name)
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Event {
+    Announce(String),
+    Value(String),
+    Unannounce(String, i32),
+}
+
+impl ServerState {
+    fn time(&self) -> u64 {
+        self.start.elapsed().as_micros() as u64
+    }
+
+    ///
+    /// # Function
+    /// Adds a publisher to a topic, making the topic if it is new.
+    ///
+    /// # Returns
+    /// A copy of the topic and if it is new
+    ///
+    fn publish(
+        &mut self,
+        name: &str,
+        r#type: &str,
+        properties: &serde_json::Value,
+    ) -> (ServerTopic, bool) {
+        let next_id = self.next_id + 1;
+        let is_new = !self.topics.contains_key(name);
+        let topic = self
+            .topics
+            .entry(name.to_string())
+            .or_insert_with(|| ServerTopic {
+                id: next_id,
+                r#type: r#type.to_string(),
+                properties: match properties {
+                    serde_json::Value::Object(properties) => {
+                        serde_json::Value::Object(properties.clone())
+                    }
+                    _ => serde_json::json!({}),
+                },
+                publishers: 0,
+                value: None,
+            });
+        topic.publishers += 1;
+        let topic = topic.clone();
+        if is_new {
+            self.next_id = next_id;
+        }
+        (topic, is_new)
+    }
+}
+
+#[derive(Debug)]
+struct Subscription {
+    topics: Vec<String>,
+    prefix: bool,
+    topics_only: bool,
+}
+
+impl Subscription {
+    fn matches(&self, name: &str) -> bool {
+        self.topics.iter().any(|topic| {
+            if self.prefix {
+                name.starts_with(topic.as_str())
+            } else {
+                name == topic
+            }
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClientState {
+    subscriptions: HashMap<i64, Subscription>,
+    /// The topics that were announced to this client
+    announced: HashSet<String>,
+    /// pubuid -> topic name
+    published: HashMap<i64, String>,
+}
+
+impl Nt4Server {
+    pub(crate) fn new(recorder: Option<Recorder>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ServerState {
+                start: Instant::now(),
+                start_wall: now_micros(),
+                next_id: 0,
+                topics: HashMap::new(),
+            })),
+            events: broadcast::channel(1024).0,
+            recorder,
+        }
+    }
+
+    ///
+    /// # Function
+    /// Talks to one client until it disconnects. The topics that the client published go away with it.
+    ///
+    /// # Parameters
+    /// - `stream`: The connection of the client, the WebSocket handshake is done here
+    ///
+    pub(crate) async fn handle_client(self, stream: TcpStream) {
+        let Ok(websocket) = tokio_tungstenite::accept_hdr_async(stream, echo_protocol).await else {
+            return;
+        };
+        let (mut write, mut read) = websocket.split();
+        let mut events = self.events.subscribe();
+        let mut client = ClientState::default();
+
+        loop {
+            let outgoing = tokio::select! {
+                incoming = read.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => self.handle_text(&text, &mut client).await,
+                    Some(Ok(Message::Binary(data))) => self.handle_binary(&data, &client),
+                    Some(Ok(_)) => Vec::new(),
+                    Some(Err(_)) | None => break,
+                },
+                event = events.recv() => match event {
+                    Ok(Event::Announce(name)) | Ok(Event::Value(name)) => self.send_topic(&name, &mut client),
+                    Ok(Event::Unannounce(name, id)) if client.announced.remove(&name) => {
+                        vec![text(serde_json::json!([{"method": "unannounce", "params": {"name": name, "id": id}}]))]
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => Vec::new(),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+
+            let mut closed = false;
+            for message in outgoing {
+                if write.send(message).await.is_err() {
+                    closed = true;
+                    break;
+                }
+            }
+            if closed {
+                break;
+            }
+        }
+
+        // the topics of the client go away with it
+        for (_, name) in client.published.drain() {
+            self.unpublish(&name).await;
+        }
+    }
+
+    #[cfg(test)]
+    ///
+    /// # Function
+    /// Sets the value of a topic from the server itself (announcing it first if it is new) and sends it to every subscribed
+    /// client. The server stays a publisher of the topic, so it is not unannounced when its clients stop publishing it.
+    ///
+    /// # Parameters
+    /// - `name`: The topic name
+    /// - `r#type`: The NT4 type string (e.g. `double`)
+    /// - `value`: The value
+    ///
+    pub(crate) async fn publish(&self, name: &str, r#type: &str, value: Value) {
+        let (topic, is_new) = {
+            let mut state = self.state.lock().unwrap();
+            match state.topics.get(name) {
+                Some(topic) => (topic.clone(), false),
+                None => state.publish(name, r#type, &serde_json::Value::Null),
+            }
+        };
+        if is_new {
+            self.record_announce(name, &topic).await;
+            let _ = self.events.send(Event::Announce(name.to_string()));
+        }
+        self.set_value(name, None, value);
+    }
+
+    #[cfg(test)]
+    ///
+    /// # Function
+    /// Removes a topic, also if it still has publishers, and tells the clients that knew about it.
+    ///
+    pub(crate) async fn unannounce(&self, name: &str) {
+        let removed = self.state.lock().unwrap().topics.remove(name);
+        if let Some(topic) = removed {
+            self.removed(name, topic).await;
+        }
+    }
+
+    #[cfg(test)]
+    ///
+    /// # Function
+    /// Gets the newest value of a topic, also if it was published by a client.
+    ///
+    pub(crate) fn value(&self, name: &str) -> Option<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .topics
+            .get(name)
+            .and_then(|topic| topic.value.clone())
+            .map(|(_, value)| value)
+    }
+
+    async fn handle_text(&self, message: &str, client: &mut ClientState) -> Vec<Message> {
+        let Ok(serde_json::Value::Array(messages)) = serde_json::from_str(message) else {
+            return Vec::new();
+        };
+
+        let mut outgoing = Vec::new();
+        for message in messages {
+            let params = &message["params"];
+            match message["method"].as_str().unwrap_or_default() {
+                "subscribe" => {
+                    let options = &params["options"];
+                    let subscription = Subscription {
+                        topics: params["topics"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|topic| topic.as_str().map(str::to_string))
+                            .collect(),
+                        prefix: options["prefix"].as_bool().unwrap_or(false),
+                        topics_only: options["topicsonly"].as_bool().unwrap_or(false),
+                    };
+                    let names: Vec<String> = self
+                        .state
+                        .lock()
+                        .unwrap()
+                        .topics
+                        .keys()
+                        .filter(|name| subscription.matches(name))
+                        .cloned()
+                        .collect();
+                    client
+                        .subscriptions
+                        .insert(params["subuid"].as_i64().unwrap_or_default(), subscription);
+                    for name in names {
+                        outgoing.extend(self.send_topic(&name, client));
+                    }
+                }
+                "unsubscribe" => {
+                    client
+                        .subscriptions
+                        .remove(&params["subuid"].as_i64().unwrap_or_default());
+                }
+                "publish" => {
+                    let name = params["name"].as_str().unwrap_or_default().to_string();
+                    let pubuid = params["pubuid"].as_i64().unwrap_or_default();
+                    let (topic, is_new) = self.state.lock().unwrap().publish(
+                        &name,
+                        params["type"].as_str().unwrap_or_default(),
+                        &params["properties"],
+                    );
+
+                    // a second publish with the same pubuid replaces the first one
+                    if let Some(old) = client.published.insert(pubuid, name.clone()) {
+                        self.unpublish(&old).await;
+                    }
+                    client.announced.insert(name.clone());
+                    outgoing.push(text(serde_json::json!([{"method": "announce", "params": {
+                        "name": name, "id": topic.id, "type": topic.r#type, "pubuid": pubuid, "properties": topic.properties
+                    }}])));
+
+                    if is_new {
+                        self.record_announce(&name, &topic).await;
+                        let _ = self.events.send(Event::Announce(name));
+                    }
+                }
+                "unpublish" => {
+                    let pubuid = params["pubuid"].as_i64().unwrap_or_default();
+                    if let Some(name) = client.published.remove(&pubuid) {
+                        self.unpublish(&name).await;
+                    }
+                }
+                "setproperties" => {
+                    let name = params["name"].as_str().unwrap_or_default().to_string();
+                    let update = params["update"].as_object().cloned().unwrap_or_default();
+                    let topic = {
+                        let mut state = self.state.lock().unwrap();
+                        state.topics.get_mut(&name).map(|topic| {
+                            if let serde_json::Value::Object(properties) = &mut topic.properties {
+                                for (key, value) in &update {
+                                    if value.is_null() {
+                                        properties.remove(key);
+                                    } else {
+                                        properties.insert(key.clone(), value.clone());
+                                    }
+                                }
+                            }
+                            topic.clone()
+                        })
+                    };
+
+                    if let Some(topic) = topic {
+                        self.record_announce(&name, &topic).await;
+                        outgoing.push(text(
+                            serde_json::json!([{"method": "properties", "params": {
+                                "name": name, "ack": true, "update": update
+                            }}]),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        outgoing
+    }
+
+    /// One publisher of a topic is gone. The topic is unannounced if it was the last one and the topic is not kept
+    async fn unpublish(&self, name: &str) {
+        let removed = {
+            let mut state = self.state.lock().unwrap();
+            match state.topics.get_mut(name) {
+                Some(topic) => {
+                    topic.publishers = topic.publishers.saturating_sub(1);
+                    if topic.publishers == 0 && !topic.is_kept() {
+                        state.topics.remove(name)
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            }
+        };
+
+        if let Some(topic) = removed {
+            self.removed(name, topic).await;
+        }
+    }
+
+    /// Tells the clients and the recorder that a topic is gone
+    async fn removed(&self, name: &str, topic: ServerTopic) {
+        let _ = self
+            .events
+            .send(Event::Unannounce(name.to_string(), topic.id));
+        if let Some(recorder) = &self.recorder {
+            recorder
+                .ingest
+                .unannounce(Some(name.to_string()), Some(recorder.name.clone()))
+                .await;
+        }
+    }
+
+    fn handle_binary(&self, data: &[u8], client: &ClientState) -> Vec<Message> {
+        let mut outgoing = Vec::new();
+        let mut cursor = Cursor::new(data);
+        while (cursor.position() as usize) < data.len() {
+            let Ok(Value::Array(frame)) = rmpv::decode::read_value(&mut cursor) else {
+                break;
+            };
+            if frame.len() != 4 {
+                continue;
+            }
+
+            match frame[0].as_i64() {
+                // time sync: answer with the server time and the client time that was sent
+                Some(-1) => {
+                    let time = self.state.lock().unwrap().time();
+                    outgoing.push(binary(-1, time, 2, frame[3].clone()));
+                }
+                Some(pubuid) => {
+                    if let Some(name) = client.published.get(&pubuid) {
+                        // `0` means that the client did not know the server time
+                        let time = frame[1].as_u64().filter(|time| *time > 0);
+                        self.set_value(name, time, frame[3].clone());
+                    }
+                }
+                None => {}
+            }
+        }
+
+        outgoing
+    }
+
+    ///
+    /// # Function
+    /// Sets the value of a topic, records it and sends it to the subscribed clients.
+    ///
+    /// # Parameters
+    /// - `name`: The topic name, nothing happens if the topic is not announced
+    /// - `time`: The server time of the value. Now if `None`
+    /// - `value`: The value
+    ///
+    fn set_value(&self, name: &str, time: Option<u64>, value: Value) {
+        let entree = {
+            let mut state = self.state.lock().unwrap();
+            let time = time.unwrap_or_else(|| state.time());
+            let start_wall = state.start_wall;
+            let Some(topic) = state.topics.get_mut(name) else {
+                return;
+            };
+            topic.value = Some((time, value.clone()));
+
+            self.recorder.as_ref().map(|recorder| {
+                let r#type = Type::from_str(&topic.r#type).unwrap_or(Type::Raw);
+                let mut entree = TableEntree::from_value(
+                    name.to_string(),
+                    topic.r#type.clone(),
+                    r#type,
+                    &value,
+                    time,
+                );
+                entree.wall_time = Some(start_wall + time);
+                entree.source = Some(recorder.name.clone());
+                (recorder, entree)
+            })
+        };
+
+        if let Some((recorder, entree)) = entree {
+            recorder.ingest.push(entree);
+        }
+        let _ = self.events.send(Event::Value(name.to_string()));
+    }
+
+    /// Announces a topic to a client if it is subscribed and has not seen it yet, and sends its value if it has one
+    fn send_topic(&self, name: &str, client: &mut ClientState) -> Vec<Message> {
+        let subscriptions: Vec<&Subscription> = client
+            .subscriptions
+            .values()
+            .filter(|subscription| subscription.matches(name))
+            .collect();
+        if subscriptions.is_empty() {
+            return Vec::new();
+        }
+        let wants_value = subscriptions
+            .iter()
+            .any(|subscription| !subscription.topics_only);
+
+        let Some(topic) = self.state.lock().unwrap().topics.get(name).cloned() else {
+            return Vec::new();
+        };
+
+        let mut outgoing = Vec::new();
+        if client.announced.insert(name.to_string()) {
+            outgoing.push(text(serde_json::json!([{"method": "announce", "params": {
+                "name": name, "id": topic.id, "type": topic.r#type, "properties": topic.properties
+            }}])));
+        }
+        if let (true, Some((time, value))) = (wants_value, topic.value) {
+            // struct and protobuf topics (and their schemas) are raw data
+            let type_id = Type::from_str(&topic.r#type).unwrap_or(Type::Raw).as_u8();
+            outgoing.push(binary(topic.id as i64, time, type_id, value));
+        }
+
+        outgoing
+    }
+
+    /// Adds (or updates) a topic in the catalog of the recorder
+    async fn record_announce(&self, name: &str, topic: &ServerTopic) {
+        if let Some(recorder) = &self.recorder {
+            let mut info = TopicInfo::from_properties(
+                name.to_string(),
+                topic.r#type.clone(),
+                topic.id,
+                topic.properties.clone(),
+            );
+            info.source = Some(recorder.name.clone());
+            recorder.ingest.announce(info).await;
+        }
+    }
+}
+
+/// The client only accepts the connection if its subprotocol is sent back
+#[allow(clippy::result_large_err)] // the signature that tungstenite wants
+fn echo_protocol(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+    if let Some(protocol) = request.headers().get("Sec-WebSocket-Protocol") {
+        let protocol = protocol
+            .to_str()
+            .unwrap_or_default()
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        if let Ok(protocol) = protocol.parse() {
+            response
+                .headers_mut()
+                .insert("Sec-WebSocket-Protocol", protocol);
+        }
+    }
+    Ok(response)
+}
+
+fn text(value: serde_json::Value) -> Message {
+    Message::Text(value.to_string())
+}
+
+fn binary(id: i64, time: u64, type_id: u8, value: Value) -> Message {
+    let mut buffer = Vec::new();
+    let frame = Value::Array(vec![
+        Value::from(id),
+        Value::from(time),
+        Value::from(type_id),
+        value,
+    ]);
+    rmpv::encode::write_value(&mut buffer, &frame).unwrap();
+    Message::Binary(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use network_tables::v4::{Client, SubscriptionOptions};
+
+    use super::*;
+    use crate::{
+        database::{structs::table_value::TableValue, SQLiteDatabase},
+        ingest::IngestConfig,
+        live::LiveFeed,
+    };
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_records_published_values() {
+        let database = SQLiteDatabase::new("test.db", 2).unwrap();
+        database.clear_database().unwrap();
+        let session = database.current_session();
+        let database = Arc::new(Mutex::new(database));
+        let (ingest, writer) = Ingest::new(
+            IngestConfig {
+                max_batch_delay: 10,
+                ..Default::default()
+            },
+            LiveFeed::default(),
+        );
+        tokio::spawn(writer.run(database.clone()));
+
+        let source = Nt4ServerSource::bind(Nt4ServerConfig {
+            name: "bench".to_string(),
+            port: 0,
+        })
+        .await
+        .unwrap();
+        let address: SocketAddr = format!("127.0.0.1:{}", source.local_addr().unwrap().port())
+            .parse()
+            .unwrap();
+
+        tokio::task::LocalSet::new()
+            .run_until(async move {
+                spawn_local(Box::new(source).run(ingest));
+
+                let coprocessor = Client::try_new(address).await.unwrap();
+                let dashboard = Client::try_new(address).await.unwrap();
+                let mut subscription = dashboard
+                    .subscribe_w_options(
+                        &["/Vision/"],
+                        Some(SubscriptionOptions {
+                            prefix: Some(true),
+                            ..Default::default()
+                        }),
+                    )
+                    .await
+                    .unwrap();
+
+                let topic = coprocessor
+                    .publish_topic("/Vision/TargetCount", Type::Int, None)
+                    .await
+                    .unwrap();
+                for count in [2, 3] {
+                    coprocessor
+                        .publish_value(&topic, &Value::from(count))
+                        .await
+                        .unwrap();
+                }
+
+                // the other clients get the values too
+                let relayed = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(relayed.topic_name, "/Vision/TargetCount");
+
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                {
+                    let database = database.lock().unwrap();
+                    assert_eq!(database.current_session(), session);
+                    let values = database
+                        .get_session_values(
+                            database.current_session(),
+                            "/Vision/TargetCount",
+                            Some("bench"),
+                            u64::MAX,
+                            5,
+                        )
+                        .unwrap();
+                    let values: Vec<TableValue> =
+                        values.into_iter().map(|entree| entree.value).collect();
+                    assert_eq!(values, vec![TableValue::Int(3), TableValue::Int(2)]);
+
+                    let topics = database.get_topics("").unwrap();
+                    let topic = topics
+                        .iter()
+                        .find(|topic| topic.name == "/Vision/TargetCount")
+                        .unwrap();
+                    assert!(topic.announced);
+                    assert_eq!(topic.value_type, "int");
+                    assert_eq!(topic.source.as_deref(), Some("bench"));
+                }
+
+                // the topic goes away with its publisher
+                coprocessor.unpublish(topic).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                let topics = database.lock().unwrap().get_topics("").unwrap();
+                assert!(
+                    !topics
+                        .iter()
+                        .find(|topic| topic.name == "/Vision/TargetCount")
+                        .unwrap()
+                        .announced
+                );
+            })
+            .await;
+    }
+}